@@ -0,0 +1,279 @@
+use crate::config::Config;
+use crate::stats;
+use crate::{Game, Outcome};
+use rand::distributions::Uniform;
+use rand::{Rng, RngCore};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+// a way of picking the next guess
+// the bot keeps track of the interval the secret can still be in (low and
+// high included) and asks the strategy for a guess inside it
+pub trait Strategy {
+    fn next_guess(&mut self, low: u32, high: u32, rng: &mut dyn RngCore) -> u32;
+}
+
+// always guesses the middle of the interval
+pub struct Bisection;
+
+impl Strategy for Bisection {
+    fn next_guess(&mut self, low: u32, high: u32, _rng: &mut dyn RngCore) -> u32 {
+        low + (high - low) / 2
+    }
+}
+
+// guesses any number of the interval
+pub struct RandomInInterval;
+
+impl Strategy for RandomInInterval {
+    fn next_guess(&mut self, low: u32, high: u32, rng: &mut dyn RngCore) -> u32 {
+        rng.sample(Uniform::new_inclusive(low, high))
+    }
+}
+
+// tries every number from the bottom up
+pub struct LinearScan;
+
+impl Strategy for LinearScan {
+    fn next_guess(&mut self, low: u32, _high: u32, _rng: &mut dyn RngCore) -> u32 {
+        low
+    }
+}
+
+// the strategies that can be picked from the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrategyKind {
+    Bisection,
+    Random,
+    Linear,
+}
+
+impl StrategyKind {
+    pub const ALL: [StrategyKind; 3] = [
+        StrategyKind::Bisection,
+        StrategyKind::Random,
+        StrategyKind::Linear,
+    ];
+
+    pub fn strategy(self) -> Box<dyn Strategy> {
+        match self {
+            StrategyKind::Bisection => Box::new(Bisection),
+            StrategyKind::Random => Box::new(RandomInInterval),
+            StrategyKind::Linear => Box::new(LinearScan),
+        }
+    }
+}
+
+impl FromStr for StrategyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<StrategyKind, String> {
+        match s {
+            "bisection" => Ok(StrategyKind::Bisection),
+            "random" => Ok(StrategyKind::Random),
+            "linear" => Ok(StrategyKind::Linear),
+            other => Err(format!("unknown strategy '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for StrategyKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            StrategyKind::Bisection => "bisection",
+            StrategyKind::Random => "random",
+            StrategyKind::Linear => "linear",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// the feedback of the game contradicted what the bot already knew (or the
+// strategy guessed outside the interval)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistency {
+    pub guess: u32,
+    pub low: u32,
+    pub high: u32,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "guess {} got {:?} but the secret had to be in {}..={}",
+            self.guess, self.outcome, self.low, self.high
+        )
+    }
+}
+
+impl std::error::Error for Inconsistency {}
+
+// plays a whole game with the given strategy and returns the final
+// outcome (Win or Lose)
+pub fn play<R, W>(
+    game: &mut Game<R, W>,
+    strategy: &mut dyn Strategy,
+    rng: &mut dyn RngCore,
+) -> Result<Outcome, Inconsistency>
+where
+    R: io::BufRead,
+    W: Write,
+{
+    let mut low = game.config().min;
+    let mut high = game.config().max;
+
+    loop {
+        let guess = strategy.next_guess(low, high, rng);
+        let outcome = game.step(guess);
+
+        let inconsistency = Inconsistency {
+            guess,
+            low,
+            high,
+            outcome,
+        };
+        if guess < low || guess > high {
+            return Err(inconsistency);
+        }
+
+        match outcome {
+            // narrow the interval (feedback that would leave it empty is a lie)
+            Outcome::TooSmall if guess < high => low = guess + 1,
+            Outcome::TooBig if guess > low => high = guess - 1,
+            Outcome::Win(_) | Outcome::Lose(_) => return Ok(outcome),
+            _ => return Err(inconsistency),
+        }
+    }
+}
+
+// the results of many games played by one strategy
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub strategy: StrategyKind,
+    pub games: u32,
+    pub wins: u32,
+    // number of games for each attempt count
+    pub histogram: BTreeMap<u32, u32>,
+}
+
+impl Report {
+    pub fn mean(&self) -> f64 {
+        let total: u64 = self
+            .histogram
+            .iter()
+            .map(|(attempts, games)| u64::from(*attempts) * u64::from(*games))
+            .sum();
+
+        total as f64 / f64::from(self.games)
+    }
+
+    pub fn worst(&self) -> u32 {
+        self.histogram.keys().copied().max().unwrap_or(0)
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(
+            w,
+            "{}: {} games, {} won, mean {:.2} attempts, worst {}",
+            self.strategy,
+            self.games,
+            self.wins,
+            self.mean(),
+            self.worst()
+        )?;
+        stats::write_histogram(w, &self.histogram)
+    }
+}
+
+// plays the given number of games with random secrets
+pub fn run<G: Rng>(
+    kind: StrategyKind,
+    config: Config,
+    games: u32,
+    rng: &mut G,
+) -> Result<Report, Inconsistency> {
+    let mut strategy = kind.strategy();
+    let mut report = Report {
+        strategy: kind,
+        games,
+        wins: 0,
+        histogram: BTreeMap::new(),
+    };
+
+    for _ in 0..games {
+        let mut game = Game::new(io::empty(), io::sink(), config, rng);
+
+        if let Outcome::Win(_) = play(&mut game, strategy.as_mut(), rng)? {
+            report.wins += 1;
+        }
+        *report.histogram.entry(game.attempts()).or_insert(0) += 1;
+    }
+
+    Ok(report)
+}
+
+// the most guesses bisection ever needs for the range: every guess at
+// least halves the n numbers left, so it's ceil(log2(n + 1))
+pub fn bisection_bound(config: Config) -> u32 {
+    let n = u64::from(config.max - config.min) + 1;
+
+    64 - n.leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn bisection_never_exceeds_the_bound() {
+        let config = Config::default();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for secret in config.min..=config.max {
+            let mut game = Game::with_secret(io::empty(), io::sink(), config, secret);
+            let outcome = play(&mut game, &mut Bisection, &mut rng).unwrap();
+
+            assert!(matches!(outcome, Outcome::Win(n) if n <= bisection_bound(config)));
+        }
+        assert_eq!(bisection_bound(config), 7);
+    }
+
+    #[test]
+    fn every_strategy_wins_without_a_limit() {
+        let config = Config {
+            min: 1,
+            max: 20,
+            max_attempts: None,
+        };
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for kind in StrategyKind::ALL.iter() {
+            let report = run(*kind, config, 200, &mut rng).unwrap();
+
+            assert_eq!(report.wins, 200);
+            assert!(report.worst() <= 20);
+        }
+    }
+
+    #[test]
+    fn linear_scan_worst_case_is_the_whole_range() {
+        let config = Config {
+            min: 1,
+            max: 10,
+            max_attempts: None,
+        };
+        let mut game = Game::with_secret(io::empty(), io::sink(), config, 10);
+        let mut rng = StdRng::seed_from_u64(3);
+
+        assert_eq!(
+            play(&mut game, &mut LinearScan, &mut rng),
+            Ok(Outcome::Win(10))
+        );
+    }
+}
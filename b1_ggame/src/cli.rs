@@ -0,0 +1,524 @@
+use crate::bot::StrategyKind;
+use crate::config::{Config, Difficulty};
+use crate::hints::{HintKind, HintSettings};
+use crate::multiplayer::{Setter, Setup};
+use crate::stats;
+use crate::word;
+use i5_reporting::report;
+use std::fmt;
+use std::path::PathBuf;
+
+pub const USAGE: &str =
+    "usage: b_ggame [--difficulty easy|normal|hard] [--min N] [--max N] [--max-attempts N]
+               [--player NAME] [--stats-file PATH] [--stats]
+               [--bot bisection|random|linear|all] [--games N] [--seed N]
+               [--players NAME,NAME,... [--rounds N] [--setter]]
+               [--serve ADDR] [--record FILE] [--replay FILE] [--batch FILE|-]
+               [--hints warmth,divisibility,repeats,interval|all] [--reveal-every K]
+               [--mode number|word] [--words FILE] [--verbose]";
+
+// exit statuses, so scripts can tell how a game ended
+// (the other errors exit with the sysexits codes of i5_reporting::report, which don't clash
+// with these)
+pub const EXIT_LOSE: u8 = 1;
+pub const EXIT_USAGE: u8 = report::EX_USAGE;
+// the input ended before the game was over
+pub const EXIT_EOF: u8 = 3;
+
+// how many games --bot plays when --games is not given
+pub const DEFAULT_BOT_GAMES: u32 = 1000;
+
+// everything that can go wrong while reading the command line
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArgsError {
+    Unknown(String),
+    MissingValue(String),
+    InvalidValue { flag: String, value: String },
+    EmptyRange { min: u32, max: u32 },
+    NoAttempts,
+    NoGames,
+    NoRounds,
+    // --players with less than 2 names, or --setter without --players
+    NotEnoughPlayers,
+    // two flags that can't be given together (e.g. two modes)
+    Conflict { first: String, second: String },
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgsError::Unknown(arg) => write!(f, "unknown argument '{}'", arg),
+            ArgsError::MissingValue(flag) => write!(f, "{} needs a value", flag),
+            ArgsError::InvalidValue { flag, value } => {
+                write!(f, "invalid value '{}' for {}", value, flag)
+            }
+            ArgsError::EmptyRange { min, max } => {
+                write!(
+                    f,
+                    "--min ({}) must not be greater than --max ({})",
+                    min, max
+                )
+            }
+            ArgsError::NoAttempts => write!(f, "--max-attempts must be at least 1"),
+            ArgsError::NoGames => write!(f, "--games must be at least 1"),
+            ArgsError::NoRounds => write!(f, "--rounds must be at least 1"),
+            ArgsError::NotEnoughPlayers => write!(f, "--players needs at least 2 names"),
+            ArgsError::Conflict { first, second } => {
+                write!(f, "{} can't be used with {}", second, first)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+// the flags that choose what the program does, only one of them can be given
+const MODE_FLAGS: [&str; 6] = [
+    "--stats",
+    "--bot",
+    "--players",
+    "--serve",
+    "--replay",
+    "--mode",
+];
+
+// the flags of the number game that the word game has no use for
+const NUMBER_FLAGS: [&str; 7] = [
+    "--difficulty",
+    "--min",
+    "--max",
+    "--batch",
+    "--record",
+    "--hints",
+    "--reveal-every",
+];
+
+// what the program should do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Play,
+    // print the leaderboard and the statistics instead of playing
+    Stats,
+    // let the computer play with each of these strategies
+    Bot(Vec<StrategyKind>),
+    // several players on the same terminal
+    Multiplayer(Setup),
+    // host games for tcp clients on this address
+    Serve(String),
+    // play a recorded session again and check it goes the same way
+    Replay(PathBuf),
+    // guess a word from the dictionary instead of a number
+    Word,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub mode: Mode,
+    pub config: Config,
+    // None lets the caller pick a default (e.g. the login name)
+    pub player: Option<String>,
+    pub stats_file: PathBuf,
+    // games played by each strategy in bot mode
+    pub games: u32,
+    // seeds the rng so runs can be repeated (None picks a random seed)
+    pub seed: Option<u64>,
+    // write the session to this file so it can be replayed
+    pub record: Option<PathBuf>,
+    // read the guesses from this file ("-" is stdin) without prompting
+    pub batch: Option<PathBuf>,
+    // no hint kinds means no hints and no score
+    pub hints: HintSettings,
+    // the dictionary of the word mode
+    pub words: PathBuf,
+    // print the backtrace of the error the program stops with
+    pub verbose: bool,
+}
+
+// reads the options (without the program name)
+// the difficulty preset is applied first, then --min, --max and
+// --max-attempts override single values of it
+pub fn parse<I>(args: I) -> Result<Options, ArgsError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+
+    let mut mode = Mode::Play;
+    let mut player = None;
+    let mut stats_file = PathBuf::from(stats::DEFAULT_FILE);
+    let mut difficulty = Difficulty::Normal;
+    let mut min = None;
+    let mut max = None;
+    let mut max_attempts = None;
+    let mut games = DEFAULT_BOT_GAMES;
+    let mut seed = None;
+    let mut rounds = 1;
+    let mut setter = Setter::Random;
+    let mut record = None;
+    let mut batch = None;
+    let mut hints = HintSettings::default();
+    let mut words = PathBuf::from(word::DEFAULT_DICTIONARY);
+    let mut verbose = false;
+    // the mode flag seen so far
+    let mut mode_flag: Option<String> = None;
+    // the first of NUMBER_FLAGS given
+    let mut number_flag: Option<String> = None;
+
+    while let Some(arg) = args.next() {
+        if NUMBER_FLAGS.contains(&arg.as_str()) && number_flag.is_none() {
+            number_flag = Some(arg.clone());
+        }
+        if MODE_FLAGS.contains(&arg.as_str()) {
+            if let Some(first) = mode_flag.replace(arg.clone()) {
+                return Err(ArgsError::Conflict { first, second: arg });
+            }
+        }
+
+        match arg.as_str() {
+            "--difficulty" => difficulty = value(&arg, args.next())?,
+            "--min" => min = Some(value(&arg, args.next())?),
+            "--max" => max = Some(value(&arg, args.next())?),
+            "--max-attempts" => max_attempts = Some(value(&arg, args.next())?),
+            "--player" => player = Some(value(&arg, args.next())?),
+            "--stats-file" => stats_file = value(&arg, args.next())?,
+            "--stats" => mode = Mode::Stats,
+            "--bot" => mode = Mode::Bot(strategies(&arg, args.next())?),
+            "--games" => games = value(&arg, args.next())?,
+            "--seed" => seed = Some(value(&arg, args.next())?),
+            "--players" => mode = Mode::Multiplayer(players(&arg, args.next())?),
+            "--rounds" => rounds = value(&arg, args.next())?,
+            "--setter" => setter = Setter::Players,
+            "--serve" => mode = Mode::Serve(value(&arg, args.next())?),
+            "--record" => record = Some(value(&arg, args.next())?),
+            "--replay" => mode = Mode::Replay(value(&arg, args.next())?),
+            "--batch" => batch = Some(value(&arg, args.next())?),
+            "--hints" => hints.kinds = hint_kinds(&arg, args.next())?,
+            "--reveal-every" => hints.reveal_every = value(&arg, args.next())?,
+            "--mode" => mode = game_mode(&arg, args.next())?,
+            "--words" => words = value(&arg, args.next())?,
+            "--verbose" => verbose = true,
+            _ => return Err(ArgsError::Unknown(arg)),
+        }
+    }
+
+    if let (Mode::Word, Some(flag)) = (&mode, number_flag) {
+        return Err(ArgsError::Conflict {
+            first: String::from("--mode word"),
+            second: flag,
+        });
+    }
+
+    let mut config = difficulty.config();
+
+    if let Some(min) = min {
+        config.min = min;
+    }
+    if let Some(max) = max {
+        config.max = max;
+    }
+    if let Some(max_attempts) = max_attempts {
+        if max_attempts == 0 {
+            return Err(ArgsError::NoAttempts);
+        }
+        config.max_attempts = Some(max_attempts);
+    }
+
+    if games == 0 {
+        return Err(ArgsError::NoGames);
+    }
+    if rounds == 0 {
+        return Err(ArgsError::NoRounds);
+    }
+    if hints.reveal_every == 0 {
+        return Err(ArgsError::InvalidValue {
+            flag: String::from("--reveal-every"),
+            value: String::from("0"),
+        });
+    }
+
+    match &mut mode {
+        Mode::Multiplayer(setup) => {
+            setup.rounds = rounds;
+            setup.setter = setter;
+        }
+        _ if setter == Setter::Players => return Err(ArgsError::NotEnoughPlayers),
+        _ => {}
+    }
+
+    if config.min > config.max {
+        return Err(ArgsError::EmptyRange {
+            min: config.min,
+            max: config.max,
+        });
+    }
+
+    Ok(Options {
+        mode,
+        config,
+        player,
+        stats_file,
+        games,
+        seed,
+        record,
+        batch,
+        hints,
+        words,
+        verbose,
+    })
+}
+
+// a comma separated list of at least 2 names
+fn players(flag: &str, names: Option<String>) -> Result<Setup, ArgsError> {
+    let names: String = value(flag, names)?;
+    let players: Vec<String> = names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect();
+
+    if players.len() < 2 {
+        return Err(ArgsError::NotEnoughPlayers);
+    }
+
+    Ok(Setup {
+        players,
+        rounds: 1,
+        setter: Setter::Random,
+    })
+}
+
+fn game_mode(flag: &str, name: Option<String>) -> Result<Mode, ArgsError> {
+    match name.as_deref() {
+        Some("number") => Ok(Mode::Play),
+        Some("word") => Ok(Mode::Word),
+        Some(other) => Err(ArgsError::InvalidValue {
+            flag: flag.to_string(),
+            value: other.to_string(),
+        }),
+        None => Err(ArgsError::MissingValue(flag.to_string())),
+    }
+}
+
+// a comma separated list of hint kinds or "all" of them
+fn hint_kinds(flag: &str, names: Option<String>) -> Result<Vec<HintKind>, ArgsError> {
+    let names: String = value(flag, names)?;
+
+    if names == "all" {
+        return Ok(HintKind::ALL.to_vec());
+    }
+
+    names
+        .split(',')
+        .map(|name| value(flag, Some(String::from(name.trim()))))
+        .collect()
+}
+
+// a single strategy or "all" of them
+fn strategies(flag: &str, name: Option<String>) -> Result<Vec<StrategyKind>, ArgsError> {
+    match name.as_deref() {
+        Some("all") => Ok(StrategyKind::ALL.to_vec()),
+        _ => Ok(vec![value(flag, name)?]),
+    }
+}
+
+// parses the value following a flag
+fn value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, ArgsError> {
+    let value = value.ok_or_else(|| ArgsError::MissingValue(flag.to_string()))?;
+
+    value.parse().map_err(|_| ArgsError::InvalidValue {
+        flag: flag.to_string(),
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn defaults_to_the_classic_game() {
+        let options = parse(args("")).unwrap();
+
+        assert_eq!(options.mode, Mode::Play);
+        assert_eq!(options.config, Config::default());
+        assert_eq!(options.config.max_attempts, None);
+        assert_eq!(options.stats_file, PathBuf::from(stats::DEFAULT_FILE));
+        assert!(!options.verbose);
+    }
+
+    #[test]
+    fn stats_mode() {
+        let options = parse(args("--stats --stats-file /tmp/s.json --player anna")).unwrap();
+
+        assert_eq!(options.mode, Mode::Stats);
+        assert_eq!(options.stats_file, PathBuf::from("/tmp/s.json"));
+        assert_eq!(options.player, Some(String::from("anna")));
+    }
+
+    #[test]
+    fn bot_mode() {
+        let options = parse(args("--bot linear --games 10 --seed 3")).unwrap();
+
+        assert_eq!(options.mode, Mode::Bot(vec![StrategyKind::Linear]));
+        assert_eq!(options.games, 10);
+        assert_eq!(options.seed, Some(3));
+
+        let options = parse(args("--bot all")).unwrap();
+        assert_eq!(options.mode, Mode::Bot(StrategyKind::ALL.to_vec()));
+        assert_eq!(options.games, DEFAULT_BOT_GAMES);
+    }
+
+    #[test]
+    fn multiplayer_mode() {
+        let options = parse(args("--setter --players anna,bob,carl --rounds 3")).unwrap();
+
+        assert_eq!(
+            options.mode,
+            Mode::Multiplayer(Setup {
+                players: vec![
+                    String::from("anna"),
+                    String::from("bob"),
+                    String::from("carl")
+                ],
+                rounds: 3,
+                setter: Setter::Players,
+            })
+        );
+        assert_eq!(
+            parse(args("--players anna")),
+            Err(ArgsError::NotEnoughPlayers)
+        );
+        assert_eq!(parse(args("--setter")), Err(ArgsError::NotEnoughPlayers));
+    }
+
+    #[test]
+    fn serve_mode() {
+        let options = parse(args("--serve 127.0.0.1:4000 --difficulty hard")).unwrap();
+
+        assert_eq!(options.mode, Mode::Serve(String::from("127.0.0.1:4000")));
+        assert_eq!(options.config, Difficulty::Hard.config());
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let options = parse(args("--record game.json --seed 12")).unwrap();
+
+        assert_eq!(options.mode, Mode::Play);
+        assert_eq!(options.record, Some(PathBuf::from("game.json")));
+
+        let options = parse(args("--replay game.json")).unwrap();
+        assert_eq!(options.mode, Mode::Replay(PathBuf::from("game.json")));
+    }
+
+    #[test]
+    fn batch_mode() {
+        let options = parse(args("--batch -")).unwrap();
+
+        assert_eq!(options.mode, Mode::Play);
+        assert_eq!(options.batch, Some(PathBuf::from("-")));
+    }
+
+    #[test]
+    fn verbose() {
+        let options = parse(args("--stats --verbose")).unwrap();
+
+        assert_eq!(options.mode, Mode::Stats);
+        assert!(options.verbose);
+    }
+
+    #[test]
+    fn hints() {
+        let options = parse(args("--hints warmth,repeats --reveal-every 4")).unwrap();
+
+        assert_eq!(
+            options.hints,
+            HintSettings {
+                kinds: vec![HintKind::Warmth, HintKind::Repeats],
+                reveal_every: 4,
+            }
+        );
+        assert_eq!(
+            parse(args("--hints all")).unwrap().hints.kinds,
+            HintKind::ALL.to_vec()
+        );
+        assert!(parse(args("--hints warmth,psychic")).is_err());
+        assert!(parse(args("--reveal-every 0")).is_err());
+    }
+
+    #[test]
+    fn word_mode() {
+        let options = parse(args("--mode word --words parole.txt --max-attempts 8")).unwrap();
+
+        assert_eq!(options.mode, Mode::Word);
+        assert_eq!(options.words, PathBuf::from("parole.txt"));
+        assert_eq!(options.config.max_attempts, Some(8));
+        assert_eq!(parse(args("--mode number")).unwrap().mode, Mode::Play);
+        assert!(parse(args("--mode colors")).is_err());
+
+        assert_eq!(
+            parse(args("--batch - --mode word")),
+            Err(ArgsError::Conflict {
+                first: String::from("--mode word"),
+                second: String::from("--batch"),
+            })
+        );
+        assert!(parse(args("--mode word --record game.json")).is_err());
+        assert!(parse(args("--mode word --hints all")).is_err());
+        assert!(parse(args("--mode word --difficulty hard")).is_err());
+        assert!(parse(args("--mode number --difficulty hard")).is_ok());
+    }
+
+    #[test]
+    fn flags_override_the_preset() {
+        let options = parse(args("--difficulty hard --max 500 --max-attempts 3")).unwrap();
+
+        assert_eq!(
+            options.config,
+            Config {
+                min: 1,
+                max: 500,
+                max_attempts: Some(3),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(
+            parse(args("--min 10 --max 5")),
+            Err(ArgsError::EmptyRange { min: 10, max: 5 })
+        );
+        assert_eq!(
+            parse(args("--max")),
+            Err(ArgsError::MissingValue(String::from("--max")))
+        );
+        assert_eq!(
+            parse(args("--difficulty insane")),
+            Err(ArgsError::InvalidValue {
+                flag: String::from("--difficulty"),
+                value: String::from("insane"),
+            })
+        );
+        assert_eq!(parse(args("--max-attempts 0")), Err(ArgsError::NoAttempts));
+        assert_eq!(parse(args("--bot all --games 0")), Err(ArgsError::NoGames));
+    }
+
+    #[test]
+    fn rejects_two_modes() {
+        assert_eq!(
+            parse(args("--bot all --serve 127.0.0.1:4000")),
+            Err(ArgsError::Conflict {
+                first: String::from("--bot"),
+                second: String::from("--serve"),
+            })
+        );
+        assert!(parse(args("--replay game.json --stats")).is_err());
+        assert!(parse(args("--stats --stats")).is_err());
+        assert!(parse(args("--mode word --players anna,bob")).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// difficulty presets: each one picks a range and an attempt limit
+// (normal is the classic game: 1 to 100 with as many attempts as you want)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn config(self) -> Config {
+        match self {
+            Difficulty::Easy => Config {
+                min: 1,
+                max: 50,
+                max_attempts: None,
+            },
+            Difficulty::Normal => Config {
+                min: 1,
+                max: 100,
+                max_attempts: None,
+            },
+            // 10 attempts are exactly enough to bisect 1000 numbers
+            Difficulty::Hard => Config {
+                min: 1,
+                max: 1000,
+                max_attempts: Some(10),
+            },
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            other => Err(format!(
+                "unknown difficulty '{}' (expected easy, normal or hard)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// the rules of a single game: the secret is picked in min..=max and the
+// player loses after max_attempts wrong guesses (None means no limit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    pub min: u32,
+    pub max: u32,
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Difficulty::Normal.config()
+    }
+}
@@ -0,0 +1,319 @@
+use crate::config::Config;
+use crate::Outcome;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+// a winning game starts from this score
+pub const BASE_SCORE: u32 = 100;
+// every attempt after the first one costs this much
+pub const ATTEMPT_COST: u32 = 5;
+// by default a divisibility hint shows up every 3 misses
+pub const DEFAULT_REVEAL_EVERY: u32 = 3;
+
+// the divisibility hints, in the order they are revealed (2 is the parity)
+const DIVISORS: [u32; 3] = [2, 3, 5];
+
+// the kinds of hint that can be turned on, each one with its price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintKind {
+    // warmer or colder than the previous guess
+    Warmth,
+    // parity, then divisibility by 3 and 5, every few misses
+    Divisibility,
+    // the guess was already tried
+    Repeats,
+    // the guess is outside what the feedback already ruled out
+    Interval,
+}
+
+impl HintKind {
+    pub const ALL: [HintKind; 4] = [
+        HintKind::Warmth,
+        HintKind::Divisibility,
+        HintKind::Repeats,
+        HintKind::Interval,
+    ];
+
+    // points taken from the score each time a hint of this kind is given
+    pub fn cost(self) -> u32 {
+        match self {
+            HintKind::Warmth => 2,
+            HintKind::Divisibility => 10,
+            HintKind::Repeats => 1,
+            HintKind::Interval => 1,
+        }
+    }
+}
+
+impl FromStr for HintKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<HintKind, String> {
+        match s {
+            "warmth" => Ok(HintKind::Warmth),
+            "divisibility" => Ok(HintKind::Divisibility),
+            "repeats" => Ok(HintKind::Repeats),
+            "interval" => Ok(HintKind::Interval),
+            other => Err(format!("unknown hint '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for HintKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            HintKind::Warmth => "warmth",
+            HintKind::Divisibility => "divisibility",
+            HintKind::Repeats => "repeats",
+            HintKind::Interval => "interval",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// which hints are on and how often divisibility is revealed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HintSettings {
+    pub kinds: Vec<HintKind>,
+    pub reveal_every: u32,
+}
+
+impl HintSettings {
+    pub fn enabled(&self, kind: HintKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+}
+
+impl Default for HintSettings {
+    fn default() -> HintSettings {
+        HintSettings {
+            kinds: Vec::new(),
+            reveal_every: DEFAULT_REVEAL_EVERY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    Warmer,
+    Colder,
+    Divisible { divisor: u32, divisible: bool },
+    Repeated(u32),
+    OutsideInterval { low: u32, high: u32 },
+}
+
+impl Hint {
+    pub fn kind(self) -> HintKind {
+        match self {
+            Hint::Warmer | Hint::Colder => HintKind::Warmth,
+            Hint::Divisible { .. } => HintKind::Divisibility,
+            Hint::Repeated(_) => HintKind::Repeats,
+            Hint::OutsideInterval { .. } => HintKind::Interval,
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Hint::Warmer => write!(f, "warmer"),
+            Hint::Colder => write!(f, "colder"),
+            Hint::Divisible {
+                divisor: 2,
+                divisible,
+            } => write!(
+                f,
+                "the secret is {}",
+                if *divisible { "even" } else { "odd" }
+            ),
+            Hint::Divisible { divisor, divisible } => write!(
+                f,
+                "the secret is {}divisible by {}",
+                if *divisible { "" } else { "not " },
+                divisor
+            ),
+            Hint::Repeated(guess) => write!(f, "you already tried {}", guess),
+            Hint::OutsideInterval { low, high } => write!(
+                f,
+                "you already knew the secret is between {} and {}",
+                low, high
+            ),
+        }
+    }
+}
+
+// follows a game and works out the hints after each guess
+pub struct Hinter {
+    settings: HintSettings,
+    previous: Option<u32>,
+    guessed: BTreeSet<u32>,
+    // what the feedback has told so far: the secret is in low..=high
+    low: u32,
+    high: u32,
+    misses: u32,
+    revealed: usize,
+    cost: u32,
+}
+
+impl Hinter {
+    pub fn new(settings: HintSettings, config: Config) -> Hinter {
+        Hinter {
+            settings,
+            previous: None,
+            guessed: BTreeSet::new(),
+            low: config.min,
+            high: config.max,
+            misses: 0,
+            revealed: 0,
+            cost: 0,
+        }
+    }
+
+    // the hints for a counted guess, given the feedback it got
+    // only misses get hints, there's nothing left to say after a win or a
+    // loss
+    pub fn hints(&mut self, guess: u32, outcome: Outcome, secret: u32) -> Vec<Hint> {
+        let mut hints = Vec::new();
+
+        if let Outcome::Win(_) | Outcome::Lose(_) = outcome {
+            return hints;
+        }
+
+        if !self.guessed.insert(guess) {
+            hints.push(Hint::Repeated(guess));
+        } else if guess < self.low || guess > self.high {
+            hints.push(Hint::OutsideInterval {
+                low: self.low,
+                high: self.high,
+            });
+        }
+
+        if let Some(previous) = self.previous {
+            let distance = guess.abs_diff(secret);
+
+            if distance < previous.abs_diff(secret) {
+                hints.push(Hint::Warmer);
+            } else if distance > previous.abs_diff(secret) {
+                hints.push(Hint::Colder);
+            }
+        }
+        self.previous = Some(guess);
+
+        match outcome {
+            Outcome::TooSmall => self.low = self.low.max(guess + 1),
+            Outcome::TooBig => self.high = self.high.min(guess - 1),
+            _ => {}
+        }
+
+        self.misses += 1;
+        if self.misses.is_multiple_of(self.settings.reveal_every) && self.revealed < DIVISORS.len()
+        {
+            let divisor = DIVISORS[self.revealed];
+            self.revealed += 1;
+
+            hints.push(Hint::Divisible {
+                divisor,
+                divisible: secret.is_multiple_of(divisor),
+            });
+        }
+
+        hints.retain(|hint| self.settings.enabled(hint.kind()));
+        self.cost += hints.iter().map(|hint| hint.kind().cost()).sum::<u32>();
+        hints
+    }
+
+    // points spent on hints so far
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+}
+
+// a win scores BASE_SCORE minus the extra attempts and the hints, a loss
+// scores nothing
+pub fn score(outcome: Outcome, hint_cost: u32) -> u32 {
+    match outcome {
+        Outcome::Win(attempts) => BASE_SCORE
+            .saturating_sub((attempts - 1).saturating_mul(ATTEMPT_COST))
+            .saturating_sub(hint_cost),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hinter(kinds: &[HintKind], reveal_every: u32) -> Hinter {
+        let settings = HintSettings {
+            kinds: kinds.to_vec(),
+            reveal_every,
+        };
+
+        Hinter::new(settings, Config::default())
+    }
+
+    #[test]
+    fn warmer_and_colder() {
+        let mut hinter = hinter(&[HintKind::Warmth], 100);
+
+        assert_eq!(hinter.hints(10, Outcome::TooSmall, 40), vec![]);
+        assert_eq!(hinter.hints(60, Outcome::TooBig, 40), vec![Hint::Warmer]);
+        assert_eq!(hinter.hints(90, Outcome::TooBig, 40), vec![Hint::Colder]);
+        assert_eq!(hinter.cost(), 4);
+    }
+
+    #[test]
+    fn divisibility_every_k_misses() {
+        let mut hinter = hinter(&[HintKind::Divisibility], 2);
+
+        assert_eq!(hinter.hints(1, Outcome::TooSmall, 30), vec![]);
+        assert_eq!(
+            hinter.hints(2, Outcome::TooSmall, 30),
+            vec![Hint::Divisible {
+                divisor: 2,
+                divisible: true
+            }]
+        );
+        hinter.hints(3, Outcome::TooSmall, 30);
+        assert_eq!(
+            hinter.hints(4, Outcome::TooSmall, 30),
+            vec![Hint::Divisible {
+                divisor: 3,
+                divisible: true
+            }]
+        );
+    }
+
+    #[test]
+    fn repeats_and_known_interval() {
+        let mut hinter = hinter(&[HintKind::Repeats, HintKind::Interval], 100);
+
+        hinter.hints(50, Outcome::TooBig, 30);
+        assert_eq!(
+            hinter.hints(50, Outcome::TooBig, 30),
+            vec![Hint::Repeated(50)]
+        );
+        assert_eq!(
+            hinter.hints(70, Outcome::TooBig, 30),
+            vec![Hint::OutsideInterval { low: 1, high: 49 }]
+        );
+    }
+
+    #[test]
+    fn disabled_hints_are_free() {
+        let mut hinter = hinter(&[], 1);
+
+        hinter.hints(50, Outcome::TooBig, 30);
+        assert_eq!(hinter.hints(50, Outcome::TooBig, 30), vec![]);
+        assert_eq!(hinter.cost(), 0);
+    }
+
+    #[test]
+    fn scoring() {
+        assert_eq!(score(Outcome::Win(1), 0), BASE_SCORE);
+        assert_eq!(score(Outcome::Win(3), 12), BASE_SCORE - 10 - 12);
+        assert_eq!(score(Outcome::Win(100), 0), 0);
+        assert_eq!(score(Outcome::Lose(7), 0), 0);
+    }
+}
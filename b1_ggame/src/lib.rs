@@ -0,0 +1,496 @@
+pub mod bot;
+pub mod cli;
+pub mod config;
+pub mod hints;
+pub mod multiplayer;
+pub mod record;
+pub mod server;
+pub mod stats;
+pub mod word;
+
+use config::Config;
+use hints::{HintSettings, Hinter};
+use rand::distributions::Uniform;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::num::IntErrorKind;
+
+// the feedback the game gives after each guess
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    TooSmall,
+    TooBig,
+    // the number of attempts it took to win
+    Win(u32),
+    // out of attempts, with the secret number that was not found
+    Lose(u32),
+}
+
+// why a line of input was not taken as a guess
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    NotANumber,
+    Negative,
+    OutOfRange { min: u32, max: u32 },
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rejection::NotANumber => write!(f, "not a number"),
+            Rejection::Negative => write!(f, "negative"),
+            Rejection::OutOfRange { min, max } => {
+                write!(
+                    f,
+                    "out of range (the secret is between {} and {})",
+                    min, max
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+// what happened with one line of input, see Game::play_with
+// (the types are the ones of the number game unless the game has other Rules)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turn<'a, G = u32, O = Outcome, E = Rejection> {
+    // the line exactly as it was read (newline included)
+    pub line: &'a str,
+    pub guess: Result<G, E>,
+    // None when the line was rejected
+    pub outcome: Option<O>,
+}
+
+// what changes from a guessing game to another: how a line becomes a guess, how a guess is
+// checked against the secret and what the player is told about it
+// Game runs the same prompt/read/check loop for any of them (Numbers below, word::Words for
+// the word game)
+pub trait Rules {
+    type Guess;
+    type Outcome: Clone;
+    type Rejection: fmt::Display;
+
+    // what the player is told before the first guess
+    fn intro<W: Write>(&self, output: &mut W) -> io::Result<()>;
+
+    fn parse_guess(&self, line: &str) -> Result<Self::Guess, Self::Rejection>;
+
+    // checks a guess against the secret and counts the attempt (a rejected guess is not
+    // counted)
+    fn step(&mut self, guess: &Self::Guess) -> Result<Self::Outcome, Self::Rejection>;
+
+    // tells the player what the guess got, true when the game is over
+    fn feedback<W: Write>(
+        &mut self,
+        output: &mut W,
+        guess: &Self::Guess,
+        outcome: &Self::Outcome,
+    ) -> io::Result<bool>;
+}
+
+// the rules of the classic game: a secret number in the configured range
+pub struct Numbers {
+    config: Config,
+    secret: u32,
+    attempts: u32,
+    hinter: Option<Hinter>,
+}
+
+impl Numbers {
+    pub fn new(config: Config, secret: u32) -> Numbers {
+        Numbers {
+            config,
+            secret,
+            attempts: 0,
+            hinter: None,
+        }
+    }
+
+    pub fn score(&self, outcome: Outcome) -> Option<u32> {
+        self.hinter
+            .as_ref()
+            .map(|hinter| hints::score(outcome, hinter.cost()))
+    }
+
+    // compares a guess with the secret number and counts the attempt
+    // a wrong guess that uses up the last attempt loses the game
+    pub fn check(&mut self, guess: u32) -> Outcome {
+        self.attempts += 1;
+
+        let outcome = match guess.cmp(&self.secret) {
+            Ordering::Less => Outcome::TooSmall,
+            Ordering::Greater => Outcome::TooBig,
+            Ordering::Equal => return Outcome::Win(self.attempts),
+        };
+
+        match self.config.max_attempts {
+            Some(max) if self.attempts >= max => Outcome::Lose(self.secret),
+            _ => outcome,
+        }
+    }
+}
+
+impl Rules for Numbers {
+    type Guess = u32;
+    type Outcome = Outcome;
+    type Rejection = Rejection;
+
+    fn intro<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        writeln!(
+            output,
+            "guess the number between {} and {}!",
+            self.config.min, self.config.max
+        )?;
+        if let Some(max) = self.config.max_attempts {
+            writeln!(output, "you have {} attempts", max)?;
+        }
+        Ok(())
+    }
+
+    fn parse_guess(&self, line: &str) -> Result<u32, Rejection> {
+        let out_of_range = Rejection::OutOfRange {
+            min: self.config.min,
+            max: self.config.max,
+        };
+
+        let guess: i64 = match line.trim().parse() {
+            Ok(guess) => guess,
+            Err(e) => {
+                return Err(match e.kind() {
+                    IntErrorKind::PosOverflow => out_of_range,
+                    IntErrorKind::NegOverflow => Rejection::Negative,
+                    _ => Rejection::NotANumber,
+                })
+            }
+        };
+
+        if guess < 0 {
+            return Err(Rejection::Negative);
+        }
+        if guess < i64::from(self.config.min) || guess > i64::from(self.config.max) {
+            return Err(out_of_range);
+        }
+
+        Ok(guess as u32)
+    }
+
+    fn step(&mut self, guess: &u32) -> Result<Outcome, Rejection> {
+        Ok(self.check(*guess))
+    }
+
+    fn feedback<W: Write>(
+        &mut self,
+        output: &mut W,
+        guess: &u32,
+        outcome: &Outcome,
+    ) -> io::Result<bool> {
+        writeln!(output, "you guessed: {}", guess)?;
+
+        match outcome {
+            Outcome::TooSmall => writeln!(output, "too small")?,
+            Outcome::TooBig => writeln!(output, "too big")?,
+            Outcome::Win(_) => writeln!(output, "YOU WIN")?,
+            Outcome::Lose(secret) => {
+                writeln!(output, "YOU LOSE, the secret number was {}", secret)?
+            }
+        }
+
+        if let Some(hinter) = &mut self.hinter {
+            for hint in hinter.hints(*guess, *outcome, self.secret) {
+                writeln!(output, "hint: {}", hint)?;
+            }
+        }
+
+        if let Outcome::Win(_) | Outcome::Lose(_) = outcome {
+            if let Some(score) = self.score(*outcome) {
+                writeln!(output, "score: {}", score)?;
+            }
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+// a single game: the rules (with the secret) plus the input and output it talks to
+// (any BufRead/Write pair works, so tests can use byte slices and Vec<u8>
+// instead of stdin and stdout)
+pub struct Game<R, W, T = Numbers> {
+    input: R,
+    output: W,
+    rules: T,
+    // lines read so far, to point at rejected ones
+    lines: u32,
+    prompts: bool,
+}
+
+impl<R: BufRead, W: Write> Game<R, W> {
+    // picks the secret in the configured range with the given rng (use a
+    // seeded rng for reproducible games)
+    pub fn new<G: Rng>(input: R, output: W, config: Config, rng: &mut G) -> Game<R, W> {
+        let secret = rng.sample(Uniform::new_inclusive(config.min, config.max));
+        Game::with_secret(input, output, config, secret)
+    }
+
+    pub fn with_secret(input: R, output: W, config: Config, secret: u32) -> Game<R, W> {
+        Game::with_rules(input, output, Numbers::new(config, secret))
+    }
+
+    // gives the enabled hints after each miss and scores the game
+    pub fn with_hints(mut self, settings: HintSettings) -> Game<R, W> {
+        self.rules.hinter = Some(Hinter::new(settings, self.rules.config));
+        self
+    }
+
+    pub fn secret(&self) -> u32 {
+        self.rules.secret
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.rules.attempts
+    }
+
+    pub fn config(&self) -> Config {
+        self.rules.config
+    }
+
+    // the score of a finished game, only kept when hints are on
+    pub fn score(&self, outcome: Outcome) -> Option<u32> {
+        self.rules.score(outcome)
+    }
+
+    // turns a line of input into a guess inside the configured range
+    pub fn parse_guess(&self, line: &str) -> Result<u32, Rejection> {
+        self.rules.parse_guess(line)
+    }
+
+    // see Numbers::check
+    pub fn step(&mut self, guess: u32) -> Outcome {
+        self.rules.check(guess)
+    }
+}
+
+impl<R: BufRead, W: Write, T: Rules> Game<R, W, T> {
+    pub fn with_rules(input: R, output: W, rules: T) -> Game<R, W, T> {
+        Game {
+            input,
+            output,
+            rules,
+            lines: 0,
+            prompts: true,
+        }
+    }
+
+    pub fn rules(&self) -> &T {
+        &self.rules
+    }
+
+    // leaves out the "pls input the guess" prompts (for scripted input)
+    pub fn without_prompts(mut self) -> Game<R, W, T> {
+        self.prompts = false;
+        self
+    }
+
+    // runs the prompt/read/compare loop until the game is won or lost and
+    // returns the final outcome (Win or Lose)
+    // rejected lines are reported and don't count as attempts, running out
+    // of input is an UnexpectedEof error
+    pub fn play(&mut self) -> io::Result<T::Outcome> {
+        self.play_with(|_| {})
+    }
+
+    // like play, but calls on_turn for every line read (e.g. to record the
+    // session)
+    pub fn play_with<F>(&mut self, mut on_turn: F) -> io::Result<T::Outcome>
+    where
+        F: FnMut(Turn<T::Guess, T::Outcome, T::Rejection>),
+    {
+        self.rules.intro(&mut self.output)?;
+
+        loop {
+            if self.prompts {
+                writeln!(self.output, "pls input the guess")?;
+            }
+
+            let mut line = String::new();
+
+            // read_line returns Ok(0) at the end of the input
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "input ended before the game was over",
+                ));
+            }
+            self.lines += 1;
+
+            let rules = &mut self.rules;
+            let checked = rules.parse_guess(&line).and_then(|guess| {
+                let outcome = rules.step(&guess)?;
+                Ok((guess, outcome))
+            });
+
+            let (guess, outcome) = match checked {
+                Ok(checked) => checked,
+                Err(rejection) => {
+                    writeln!(
+                        self.output,
+                        "line {} rejected ({:?}): {}",
+                        self.lines,
+                        line.trim(),
+                        rejection
+                    )?;
+                    on_turn(Turn {
+                        line: &line,
+                        guess: Err(rejection),
+                        outcome: None,
+                    });
+                    continue;
+                }
+            };
+
+            let over = self.rules.feedback(&mut self.output, &guess, &outcome)?;
+            on_turn(Turn {
+                line: &line,
+                guess: Ok(guess),
+                outcome: Some(outcome.clone()),
+            });
+
+            if over {
+                return Ok(outcome);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn step_follows_ordering() {
+        let mut game = Game::with_secret(&b""[..], Vec::new(), Config::default(), 42);
+
+        assert_eq!(game.step(10), Outcome::TooSmall);
+        assert_eq!(game.step(90), Outcome::TooBig);
+        assert_eq!(game.step(42), Outcome::Win(3));
+    }
+
+    #[test]
+    fn last_wrong_guess_loses() {
+        let config = Config {
+            min: 1,
+            max: 100,
+            max_attempts: Some(2),
+        };
+        let mut game = Game::with_secret(&b""[..], Vec::new(), config, 42);
+
+        assert_eq!(game.step(10), Outcome::TooSmall);
+        assert_eq!(game.step(90), Outcome::Lose(42));
+    }
+
+    #[test]
+    fn seeded_games_pick_the_same_secret() {
+        let config = Config {
+            min: 10,
+            max: 20,
+            max_attempts: None,
+        };
+        let a = Game::new(&b""[..], Vec::new(), config, &mut StdRng::seed_from_u64(7));
+        let b = Game::new(&b""[..], Vec::new(), config, &mut StdRng::seed_from_u64(7));
+
+        assert_eq!(a.secret(), b.secret());
+        assert!(a.secret() >= 10 && a.secret() <= 20);
+    }
+
+    #[test]
+    fn scripted_game() {
+        let mut output = Vec::new();
+        let mut game = Game::with_secret(
+            &b"50\nabc\n25\n30\n"[..],
+            &mut output,
+            Config::default(),
+            30,
+        );
+
+        assert_eq!(game.play().unwrap(), Outcome::Win(3));
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("too big"));
+        assert!(output.contains("too small"));
+        assert!(output.ends_with("YOU WIN\n"));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let config = Config {
+            min: 10,
+            max: 20,
+            max_attempts: None,
+        };
+        let game = Game::with_secret(&b""[..], Vec::new(), config, 15);
+        let out_of_range = Err(Rejection::OutOfRange { min: 10, max: 20 });
+
+        assert_eq!(game.parse_guess(" 12\n"), Ok(12));
+        assert_eq!(game.parse_guess("abc\n"), Err(Rejection::NotANumber));
+        assert_eq!(game.parse_guess("\n"), Err(Rejection::NotANumber));
+        assert_eq!(game.parse_guess("-4\n"), Err(Rejection::Negative));
+        assert_eq!(
+            game.parse_guess("-99999999999999999999\n"),
+            Err(Rejection::Negative)
+        );
+        assert_eq!(game.parse_guess("21\n"), out_of_range);
+        assert_eq!(game.parse_guess("99999999999999999999\n"), out_of_range);
+    }
+
+    #[test]
+    fn rejected_lines_are_reported_and_not_counted() {
+        let mut output = Vec::new();
+        let mut game =
+            Game::with_secret(&b"x\n-1\n500\n30\n"[..], &mut output, Config::default(), 30)
+                .without_prompts();
+
+        assert_eq!(game.play().unwrap(), Outcome::Win(1));
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("line 1 rejected (\"x\"): not a number"));
+        assert!(output.contains("line 2 rejected (\"-1\"): negative"));
+        assert!(output.contains("line 3 rejected (\"500\"): out of range"));
+        assert!(!output.contains("pls input the guess"));
+    }
+
+    #[test]
+    fn hints_and_score() {
+        let settings = HintSettings {
+            kinds: hints::HintKind::ALL.to_vec(),
+            reveal_every: 2,
+        };
+        let mut output = Vec::new();
+        let mut game =
+            Game::with_secret(&b"50\n20\n20\n30\n"[..], &mut output, Config::default(), 30)
+                .with_hints(settings);
+
+        assert_eq!(game.play().unwrap(), Outcome::Win(4));
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("hint: warmer"));
+        assert!(output.contains("hint: the secret is even"));
+        assert!(output.contains("hint: you already tried 20"));
+        // 3 extra attempts (15), warmer (2), parity (10) and a repeat (1)
+        assert!(output.ends_with("score: 72\n"));
+    }
+
+    #[test]
+    fn eof_is_an_error() {
+        let mut game = Game::with_secret(&b"1\n"[..], Vec::new(), Config::default(), 30);
+
+        let err = game.play().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -1,35 +1,243 @@
-use rand::Rng;
-use std::cmp::Ordering;
-use std::io;
+use b_ggame::bot::{self, StrategyKind};
+use b_ggame::cli::{self, Mode, Options};
+use b_ggame::multiplayer::{self, Setup};
+use b_ggame::record::{self, Recorder, Session};
+use b_ggame::server;
+use b_ggame::stats::{Record, Stats};
+use b_ggame::word::{self, Dictionary, WordGame, WordOutcome, Words};
+use b_ggame::{Game, Outcome};
+use i5_reporting::report::{self, Context, Report};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
 
-fn main() {
-    println!("guess the number!");
+fn main() -> ExitCode {
+    i5_reporting::install_crash_report!();
 
-    let secret_number = rand::thread_rng().gen_range(1, 101);
+    let options = match cli::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", cli::USAGE);
+            return ExitCode::from(cli::EXIT_USAGE);
+        }
+    };
+    report::capture_backtraces(options.verbose);
+
+    let result = match &options.mode {
+        Mode::Play => play(&options),
+        Mode::Stats => show_stats(&options),
+        Mode::Bot(strategies) => run_bots(&options, strategies),
+        Mode::Multiplayer(setup) => play_multiplayer(&options, setup.clone()),
+        Mode::Serve(addr) => serve(&options, addr),
+        Mode::Replay(path) => replay(path),
+        Mode::Word => play_words(&options),
+    };
 
-    loop {
-        println!("pls input the guess");
+    report::finish(result, options.verbose)
+}
 
-        let mut guess = String::new();
+fn play(options: &Options) -> Result<ExitCode, Report> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
 
-        io::stdin()
-            .read_line(&mut guess)
-            .expect("failed to read line");
+    let input: Box<dyn BufRead> = match &options.batch {
+        Some(path) if path != Path::new("-") => {
+            let file =
+                File::open(path).with_context(|| format!("could not read {}", path.display()))?;
+            Box::new(BufReader::new(file))
+        }
+        _ => Box::new(stdin.lock()),
+    };
 
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num,
-            Err(_) => continue,
-        };
+    // the secret always comes from a seed so the session can be recorded
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut game = Game::new(
+        input,
+        stdout.lock(),
+        options.config,
+        &mut StdRng::seed_from_u64(seed),
+    );
+    if options.batch.is_some() {
+        game = game.without_prompts();
+    }
+    if !options.hints.kinds.is_empty() {
+        game = game.with_hints(options.hints.clone());
+    }
 
-        println!("you guessed: {}", guess);
+    let start = Instant::now();
+    let outcome = match &options.record {
+        Some(path) => {
+            let mut recorder = Recorder::new(seed, options.config);
+            let outcome = game.play_with(|turn| recorder.record(turn));
 
-        match guess.cmp(&secret_number) {
-            Ordering::Less => println!("too small"),
-            Ordering::Greater => println!("too big"),
-            Ordering::Equal => {
-                println!("YOU WIN");
-                break;
+            let session = recorder.finish(outcome.as_ref().ok().copied());
+            if let Err(e) = session.save(path) {
+                eprintln!("could not save {}: {}", path.display(), e);
             }
+            outcome
         }
+        None => game.play(),
+    };
+    let outcome = read_input(outcome)?;
+
+    let record = Record {
+        player: player_name(options),
+        won: matches!(outcome, Outcome::Win(_)),
+        attempts: game.attempts(),
+        min: options.config.min,
+        max: options.config.max,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    };
+
+    // a broken stats file should not spoil the game that was just played
+    let mut stats = Stats::load(&options.stats_file).unwrap_or_else(|e| {
+        eprintln!("could not read {}: {}", options.stats_file.display(), e);
+        Stats::default()
+    });
+    stats.add(record);
+    if let Err(e) = stats.save(&options.stats_file) {
+        eprintln!("could not save {}: {}", options.stats_file.display(), e);
+    }
+
+    match outcome {
+        Outcome::Lose(_) => Ok(ExitCode::from(cli::EXIT_LOSE)),
+        _ => Ok(ExitCode::SUCCESS),
     }
 }
+
+fn show_stats(options: &Options) -> Result<ExitCode, Report> {
+    let stats = Stats::load(&options.stats_file)
+        .with_context(|| format!("could not read {}", options.stats_file.display()))?;
+
+    stats
+        .report(&mut io::stdout())
+        .context("failed to write stats")?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_bots(options: &Options, strategies: &[StrategyKind]) -> Result<ExitCode, Report> {
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    println!(
+        "{} games per strategy in {}..={} (seed {})",
+        options.games, options.config.min, options.config.max, seed
+    );
+    println!(
+        "bisection needs at most {} guesses",
+        bot::bisection_bound(options.config)
+    );
+
+    for kind in strategies {
+        println!();
+        bot::run(*kind, options.config, options.games, &mut rng)
+            .with_context(|| format!("{}: inconsistent feedback", kind))?
+            .write(&mut io::stdout())
+            .context("failed to write report")?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn play_multiplayer(options: &Options, setup: Setup) -> Result<ExitCode, Report> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    read_input(multiplayer::play(
+        stdin.lock(),
+        stdout.lock(),
+        options.config,
+        setup,
+        &mut rand::thread_rng(),
+        &mut rpassword::read_password,
+    ))?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn serve(options: &Options, addr: &str) -> Result<ExitCode, Report> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("could not listen on {}", addr))?;
+
+    eprintln!(
+        "listening on {} (GUESS n -> LOW | HIGH | WIN k)",
+        listener
+            .local_addr()
+            .context("failed to read local address")?
+    );
+    server::serve(listener, options.config).context("failed to accept clients")?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn replay(path: &Path) -> Result<ExitCode, Report> {
+    let session =
+        Session::load(path).with_context(|| format!("could not read {}", path.display()))?;
+
+    match record::replay(&session, io::stdout()) {
+        Ok(turns) => {
+            println!("\nreplay matches the recording ({} turns)", turns);
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(e) => {
+            // ends the line of the replay before the error is printed
+            println!();
+            // the recording is the bad data
+            Err(e)
+                .context("replay does not match the recording")
+                .map_err(|e| e.with_code(report::EX_DATAERR))
+        }
+    }
+}
+
+fn play_words(options: &Options) -> Result<ExitCode, Report> {
+    let dictionary = Dictionary::load(&options.words)
+        .with_context(|| format!("could not read {}", options.words.display()))?;
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    let words = Words::new(
+        dictionary,
+        options
+            .config
+            .max_attempts
+            .unwrap_or(word::DEFAULT_ATTEMPTS),
+        &mut StdRng::seed_from_u64(options.seed.unwrap_or_else(rand::random)),
+    )
+    .with_context(|| format!("could not pick a word from {}", options.words.display()))?;
+    let mut game = WordGame::with_rules(stdin.lock(), stdout.lock(), words);
+
+    match read_input(game.play())? {
+        WordOutcome::Lose(_) => Ok(ExitCode::from(cli::EXIT_LOSE)),
+        _ => Ok(ExitCode::SUCCESS),
+    }
+}
+
+// running out of input gets its own exit status, any other error reading it is reported
+fn read_input<T>(result: io::Result<T>) -> Result<T, Report> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            // the prompt is still on the last line
+            eprintln!();
+            Err(Report::new(e).with_code(cli::EXIT_EOF))
+        }
+        result => result.context("failed to read line"),
+    }
+}
+
+// --player, then the login name, then a generic name
+fn player_name(options: &Options) -> String {
+    options
+        .player
+        .clone()
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_else(|| String::from("player"))
+}
@@ -0,0 +1,307 @@
+use crate::config::Config;
+use crate::{Game, Outcome};
+use rand::distributions::Uniform;
+use rand::Rng;
+use std::io::{self, BufRead, Write};
+
+// clears the terminal so the next player can't see the previous guesses
+// it's only written when a turn starts: the last guess of a round (and its YOU WIN) stays on
+// screen with the secret and the scoreboard
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+// how the secret of each round is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setter {
+    // the computer picks it and every player guesses (hot-seat)
+    Random,
+    // the players take turns typing it and everybody else guesses
+    Players,
+}
+
+// who plays, for how long and who picks the secrets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setup {
+    pub players: Vec<String>,
+    pub rounds: u32,
+    pub setter: Setter,
+}
+
+// what each player did in each round
+// None means the player sat the round out (they were the setter)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoreboard {
+    pub players: Vec<String>,
+    pub rounds: Vec<Vec<Option<Outcome>>>,
+}
+
+impl Scoreboard {
+    pub fn new(players: Vec<String>) -> Scoreboard {
+        Scoreboard {
+            players,
+            rounds: Vec::new(),
+        }
+    }
+
+    // the players that won the round with the fewest guesses (more than
+    // one on a tie, none if everybody lost)
+    pub fn round_winners(&self, round: usize) -> Vec<usize> {
+        let attempts: Vec<Option<u32>> = self.rounds[round]
+            .iter()
+            .map(|outcome| match outcome {
+                Some(Outcome::Win(n)) => Some(*n),
+                _ => None,
+            })
+            .collect();
+
+        let best = match attempts.iter().flatten().min() {
+            Some(best) => *best,
+            None => return Vec::new(),
+        };
+
+        (0..attempts.len())
+            .filter(|i| attempts[*i] == Some(best))
+            .collect()
+    }
+
+    // rounds won by each player
+    pub fn points(&self) -> Vec<u32> {
+        let mut points = vec![0; self.players.len()];
+
+        for round in 0..self.rounds.len() {
+            for winner in self.round_winners(round) {
+                points[winner] += 1;
+            }
+        }
+
+        points
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "{:<8}", "round")?;
+        for player in &self.players {
+            write!(w, " {:>10}", player)?;
+        }
+        writeln!(w)?;
+
+        for (i, round) in self.rounds.iter().enumerate() {
+            let winners = self.round_winners(i);
+
+            write!(w, "{:<8}", i + 1)?;
+            for (p, outcome) in round.iter().enumerate() {
+                let cell = match outcome {
+                    Some(Outcome::Win(n)) if winners.contains(&p) => format!("*{}", n),
+                    Some(Outcome::Win(n)) => n.to_string(),
+                    Some(_) => String::from("lost"),
+                    None => String::from("-"),
+                };
+                write!(w, " {:>10}", cell)?;
+            }
+            writeln!(w)?;
+        }
+
+        write!(w, "{:<8}", "won")?;
+        for points in self.points() {
+            write!(w, " {:>10}", points)?;
+        }
+        writeln!(w)
+    }
+}
+
+// asks the setter for the secret until it's a number in the range
+// the secret is read with read_hidden so it doesn't show up on screen
+pub fn read_secret<W, F>(output: &mut W, config: Config, read_hidden: &mut F) -> io::Result<u32>
+where
+    W: Write,
+    F: FnMut() -> io::Result<String>,
+{
+    loop {
+        writeln!(
+            output,
+            "type the secret number between {} and {} (it won't be shown)",
+            config.min, config.max
+        )?;
+        output.flush()?;
+
+        let secret = read_hidden()?;
+
+        match secret.trim().parse() {
+            Ok(secret) if secret >= config.min && secret <= config.max => return Ok(secret),
+            _ => writeln!(
+                output,
+                "that is not a number between {} and {}",
+                config.min, config.max
+            )?,
+        }
+    }
+}
+
+// plays the given number of rounds, printing the scoreboard after each one
+pub fn play<R, W, G, F>(
+    mut input: R,
+    mut output: W,
+    config: Config,
+    setup: Setup,
+    rng: &mut G,
+    read_hidden: &mut F,
+) -> io::Result<Scoreboard>
+where
+    R: BufRead,
+    W: Write,
+    G: Rng,
+    F: FnMut() -> io::Result<String>,
+{
+    let mut scoreboard = Scoreboard::new(setup.players);
+    let count = scoreboard.players.len();
+
+    for round in 0..setup.rounds as usize {
+        let setter = match setup.setter {
+            Setter::Random => None,
+            Setter::Players => Some(round % count),
+        };
+        let secret = match setter {
+            Some(p) => {
+                writeln!(
+                    output,
+                    "round {}: {} sets the secret",
+                    round + 1,
+                    scoreboard.players[p]
+                )?;
+                read_secret(&mut output, config, read_hidden)?
+            }
+            None => rng.sample(Uniform::new_inclusive(config.min, config.max)),
+        };
+
+        let mut results = Vec::with_capacity(count);
+        for p in 0..count {
+            if Some(p) == setter {
+                results.push(None);
+                continue;
+            }
+
+            writeln!(
+                output,
+                "{}round {}: {}'s turn",
+                CLEAR_SCREEN,
+                round + 1,
+                scoreboard.players[p]
+            )?;
+            let mut game = Game::with_secret(&mut input, &mut output, config, secret);
+            results.push(Some(game.play()?));
+        }
+        scoreboard.rounds.push(results);
+
+        writeln!(output, "the secret number was {}\n", secret)?;
+        scoreboard.write(&mut output)?;
+    }
+
+    Ok(scoreboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn players(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| String::from(*name)).collect()
+    }
+
+    fn no_hidden_input() -> io::Result<String> {
+        panic!("nobody sets the secret in hot-seat mode")
+    }
+
+    #[test]
+    fn hot_seat_players_share_the_secret() {
+        let config = Config {
+            min: 1,
+            max: 2,
+            max_attempts: None,
+        };
+        // the same draw play makes for the round
+        let secret = StdRng::seed_from_u64(1).sample(Uniform::new_inclusive(1, 2));
+        let input = format!("{}\n{}\n{}\n", secret, 3 - secret, secret);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let mut output = Vec::new();
+
+        let scoreboard = play(
+            input.as_bytes(),
+            &mut output,
+            config,
+            Setup {
+                players: players(&["anna", "bob"]),
+                rounds: 1,
+                setter: Setter::Random,
+            },
+            &mut rng,
+            &mut no_hidden_input,
+        )
+        .unwrap();
+
+        assert_eq!(
+            scoreboard.rounds,
+            vec![vec![Some(Outcome::Win(1)), Some(Outcome::Win(2))]]
+        );
+        assert_eq!(scoreboard.points(), vec![1, 0]);
+
+        // the screen is cleared before each turn, and not after the last guess
+        let output = String::from_utf8(output).unwrap();
+        let screens: Vec<&str> = output.split(CLEAR_SCREEN).collect();
+        assert_eq!(screens.len(), 3);
+        assert!(screens[1].starts_with("round 1: anna's turn"));
+        assert!(screens[2].starts_with("round 1: bob's turn"));
+        assert!(screens[2].contains("YOU WIN\nthe secret number was"));
+    }
+
+    #[test]
+    fn setters_take_turns() {
+        let mut secrets = vec!["abc", "500", "30", "60"].into_iter();
+        let mut read_hidden = || Ok(String::from(secrets.next().unwrap()));
+        let mut rng = StdRng::seed_from_u64(1);
+
+        // bob guesses 30 in 2, then anna guesses 60 in 2 as well
+        let scoreboard = play(
+            &b"50\n30\n50\n60\n"[..],
+            io::sink(),
+            Config::default(),
+            Setup {
+                players: players(&["anna", "bob"]),
+                rounds: 2,
+                setter: Setter::Players,
+            },
+            &mut rng,
+            &mut read_hidden,
+        )
+        .unwrap();
+
+        assert_eq!(
+            scoreboard.rounds,
+            vec![
+                vec![None, Some(Outcome::Win(2))],
+                vec![Some(Outcome::Win(2)), None],
+            ]
+        );
+        assert_eq!(scoreboard.points(), vec![1, 1]);
+    }
+
+    #[test]
+    fn ties_and_losses() {
+        let scoreboard = Scoreboard {
+            players: players(&["a", "b", "c"]),
+            rounds: vec![vec![
+                Some(Outcome::Win(3)),
+                Some(Outcome::Lose(9)),
+                Some(Outcome::Win(3)),
+            ]],
+        };
+
+        assert_eq!(scoreboard.round_winners(0), vec![0, 2]);
+
+        let mut table = Vec::new();
+        scoreboard.write(&mut table).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        assert!(table.contains("*3"));
+        assert!(table.contains("lost"));
+    }
+}
@@ -0,0 +1,280 @@
+use crate::config::Config;
+use crate::{Game, Outcome, Turn};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// one line of input and what the game made of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    // milliseconds since the session started
+    pub elapsed_ms: u64,
+    pub line: String,
+    // the parsed guess or the reason the line was skipped
+    pub guess: Result<u32, String>,
+    pub outcome: Option<Outcome>,
+}
+
+impl Event {
+    fn from_turn(turn: Turn, elapsed_ms: u64) -> Event {
+        Event {
+            elapsed_ms,
+            line: String::from(turn.line),
+            guess: turn.guess.map_err(|e| e.to_string()),
+            outcome: turn.outcome,
+        }
+    }
+
+    // same input and same feedback, whatever the timing
+    fn matches(&self, other: &Event) -> bool {
+        self.line == other.line && self.guess == other.guess && self.outcome == other.outcome
+    }
+}
+
+// everything needed to play a game again exactly as it went
+// (the secret comes from seeding a StdRng, so a recording only replays with
+// the same version of rand)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub seed: u64,
+    pub config: Config,
+    // seconds since the unix epoch
+    pub started: u64,
+    pub events: Vec<Event>,
+    // None if the input ended before the game did
+    pub result: Option<Outcome>,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> io::Result<Session> {
+        let file = BufReader::new(File::open(path)?);
+
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()
+    }
+
+    // the rng the secret of this session was picked with
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
+}
+
+// collects the turns of a game while it's being played
+pub struct Recorder {
+    session: Session,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn new(seed: u64, config: Config) -> Recorder {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Recorder {
+            session: Session {
+                seed,
+                config,
+                started,
+                events: Vec::new(),
+                result: None,
+            },
+            start: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, turn: Turn) {
+        let elapsed_ms = self.start.elapsed().as_millis() as u64;
+
+        self.session.events.push(Event::from_turn(turn, elapsed_ms));
+    }
+
+    pub fn finish(mut self, result: Option<Outcome>) -> Session {
+        self.session.result = result;
+        self.session
+    }
+}
+
+// why a replay did not go like the recording
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Turn {
+        index: usize,
+        expected: Box<Event>,
+        actual: Box<Event>,
+    },
+    Length {
+        expected: usize,
+        actual: usize,
+    },
+    Result {
+        expected: Option<Outcome>,
+        actual: Option<Outcome>,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "replay failed: {}", e),
+            ReplayError::Turn {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "turn {} differs: recorded {:?} -> {:?} {:?}, replayed {:?} -> {:?} {:?}",
+                index + 1,
+                expected.line,
+                expected.guess,
+                expected.outcome,
+                actual.line,
+                actual.guess,
+                actual.outcome
+            ),
+            ReplayError::Length { expected, actual } => write!(
+                f,
+                "recorded {} turns but the replay took {}",
+                expected, actual
+            ),
+            ReplayError::Result { expected, actual } => write!(
+                f,
+                "recorded result {:?} but the replay ended with {:?}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> ReplayError {
+        ReplayError::Io(e)
+    }
+}
+
+// plays the recorded input again with the recorded seed and checks that
+// every turn and the result come out the same
+// returns the number of turns that were checked
+pub fn replay<W: Write>(session: &Session, output: W) -> Result<usize, ReplayError> {
+    let input: String = session.events.iter().map(|e| e.line.as_str()).collect();
+    let mut game = Game::new(input.as_bytes(), output, session.config, &mut session.rng());
+
+    let mut events = Vec::new();
+    let result = match game.play_with(|turn| events.push(Event::from_turn(turn, 0))) {
+        Ok(outcome) => Some(outcome),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => None,
+        Err(e) => return Err(ReplayError::Io(e)),
+    };
+
+    for (index, (expected, actual)) in session.events.iter().zip(&events).enumerate() {
+        if !expected.matches(actual) {
+            return Err(ReplayError::Turn {
+                index,
+                expected: Box::new(expected.clone()),
+                actual: Box::new(actual.clone()),
+            });
+        }
+    }
+
+    if events.len() != session.events.len() {
+        return Err(ReplayError::Length {
+            expected: session.events.len(),
+            actual: events.len(),
+        });
+    }
+
+    if result != session.result {
+        return Err(ReplayError::Result {
+            expected: session.result,
+            actual: result,
+        });
+    }
+
+    Ok(events.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(seed: u64, input: &str) -> Session {
+        let config = Config::default();
+        let mut game = Game::new(
+            input.as_bytes(),
+            io::sink(),
+            config,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        let mut recorder = Recorder::new(seed, config);
+
+        let result = game.play_with(|turn| recorder.record(turn)).ok();
+        recorder.finish(result)
+    }
+
+    #[test]
+    fn records_parse_errors_and_feedback() {
+        let session = record(5, "abc\n-3\n50\n");
+
+        assert_eq!(session.events.len(), 3);
+        assert_eq!(session.events[0].line, "abc\n");
+        assert_eq!(session.events[0].guess, Err(String::from("not a number")));
+        assert_eq!(session.events[1].guess, Err(String::from("negative")));
+        assert_eq!(session.events[2].guess, Ok(50));
+        assert!(session.events[2].outcome.is_some());
+        assert_eq!(session.result, None);
+    }
+
+    #[test]
+    fn replay_matches_the_recording() {
+        let secret = Game::new(
+            io::empty(),
+            io::sink(),
+            Config::default(),
+            &mut StdRng::seed_from_u64(9),
+        )
+        .secret();
+        let session = record(9, &format!("x\n{}\n", secret));
+
+        assert_eq!(session.result, Some(Outcome::Win(1)));
+        assert_eq!(replay(&session, io::sink()).unwrap(), 2);
+    }
+
+    #[test]
+    fn replay_detects_tampering() {
+        let mut session = record(3, "10\n20\n");
+        session.seed += 1;
+        session.events[0].outcome = Some(Outcome::Win(1));
+
+        match replay(&session, io::sink()) {
+            Err(ReplayError::Turn { index: 0, .. }) => {}
+            other => panic!("expected a turn mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("ggame_session_{}.json", std::process::id()));
+        let session = record(1, "1\n2\n");
+
+        session.save(&path).unwrap();
+        let loaded = Session::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, session);
+    }
+}
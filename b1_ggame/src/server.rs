@@ -0,0 +1,253 @@
+// a line protocol to play the game over tcp (try it with nc)
+//
+// client                 server
+// GUESS n            ->  LOW | HIGH | WIN k | LOSE secret
+// QUIT               ->  (closes the connection)
+// anything else      ->  ERR message
+//
+// LOW means the guess is lower than the secret, k is the number of attempts
+// it took to win; after WIN or LOSE a new secret is picked so the client can
+// keep playing
+// a guess outside the range gets ERR and doesn't count as an attempt, like
+// in the terminal game
+// a line longer than MAX_LINE gets ERR and closes the connection (otherwise a
+// client could fill the memory of the server with a line that never ends)
+
+use crate::config::Config;
+use crate::{Game, Outcome};
+use rand::Rng;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+// the longest line a client can send, newline excluded
+pub const MAX_LINE: usize = 256;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Request {
+    // the guess as it was sent, checked by Game::parse_guess
+    Guess(String),
+    Quit,
+}
+
+pub fn parse_request(line: &str) -> Result<Request, String> {
+    let mut words = line.split_whitespace();
+
+    match (words.next(), words.next(), words.next()) {
+        (Some(command), Some(n), None) if command.eq_ignore_ascii_case("GUESS") => {
+            Ok(Request::Guess(String::from(n)))
+        }
+        (Some(command), None, None) if command.eq_ignore_ascii_case("QUIT") => Ok(Request::Quit),
+        _ => Err(String::from("expected GUESS n or QUIT")),
+    }
+}
+
+pub fn response(outcome: Outcome) -> String {
+    match outcome {
+        Outcome::TooSmall => String::from("LOW"),
+        Outcome::TooBig => String::from("HIGH"),
+        Outcome::Win(attempts) => format!("WIN {}", attempts),
+        Outcome::Lose(secret) => format!("LOSE {}", secret),
+    }
+}
+
+// talks to one client until it quits or disconnects
+pub fn handle_session<R, W, G>(
+    input: R,
+    mut output: W,
+    config: Config,
+    rng: &mut G,
+) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+    G: Rng,
+{
+    let mut input = input;
+    let mut game = Game::new(io::empty(), io::sink(), config, rng);
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+
+        // room for MAX_LINE bytes and the newline: a line that fills it without ending is too
+        // long
+        let read = input
+            .by_ref()
+            .take(MAX_LINE as u64 + 1)
+            .read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        if line.len() > MAX_LINE && !line.ends_with(b"\n") {
+            writeln!(output, "ERR line longer than {} bytes", MAX_LINE)?;
+            output.flush()?;
+            break;
+        }
+
+        let reply = match parse_request(&String::from_utf8_lossy(&line)) {
+            Ok(Request::Guess(guess)) => match game.parse_guess(&guess) {
+                Ok(guess) => {
+                    let outcome = game.step(guess);
+                    if let Outcome::Win(_) | Outcome::Lose(_) = outcome {
+                        game = Game::new(io::empty(), io::sink(), config, rng);
+                    }
+                    response(outcome)
+                }
+                Err(rejection) => format!("ERR {}", rejection),
+            },
+            Ok(Request::Quit) => break,
+            Err(e) => format!("ERR {}", e),
+        };
+
+        writeln!(output, "{}", reply)?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+// accepts clients forever, each one in its own thread with its own secret
+pub fn serve(listener: TcpListener, config: Config) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("failed to accept a client: {}", e);
+                continue;
+            }
+        };
+
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_else(|_| String::from("unknown"));
+            eprintln!("{} connected", peer);
+
+            match client(stream, config) {
+                Ok(()) => eprintln!("{} disconnected", peer),
+                Err(e) => eprintln!("{} dropped: {}", peer, e),
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn client(stream: TcpStream, config: Config) -> io::Result<()> {
+    let input = BufReader::new(stream.try_clone()?);
+
+    handle_session(input, stream, config, &mut rand::thread_rng())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn parses_requests() {
+        assert_eq!(
+            parse_request("GUESS 42"),
+            Ok(Request::Guess(String::from("42")))
+        );
+        assert_eq!(
+            parse_request("  guess 7\r"),
+            Ok(Request::Guess(String::from("7")))
+        );
+        assert_eq!(parse_request("QUIT"), Ok(Request::Quit));
+        assert!(parse_request("GUESS 1 2").is_err());
+        assert!(parse_request("").is_err());
+    }
+
+    #[test]
+    fn session_replies_line_by_line() {
+        let config = Config {
+            min: 1,
+            max: 1,
+            max_attempts: None,
+        };
+        let mut output = Vec::new();
+
+        handle_session(
+            &b"GUESS 2\nGUESS abc\nhello\nGUESS 1\nQUIT\nGUESS 1\n"[..],
+            &mut output,
+            config,
+            &mut StdRng::seed_from_u64(1),
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "ERR out of range (the secret is between 1 and 1)\n\
+             ERR not a number\n\
+             ERR expected GUESS n or QUIT\n\
+             WIN 1\n"
+        );
+    }
+
+    #[test]
+    fn long_lines_close_the_session() {
+        let fits = format!("GUESS {}\n", "0".repeat(MAX_LINE - 6));
+        let too_long = format!("GUESS {}\nGUESS 1\n", "0".repeat(MAX_LINE));
+        let mut output = Vec::new();
+
+        handle_session(
+            (fits + &too_long).as_bytes(),
+            &mut output,
+            Config::default(),
+            &mut StdRng::seed_from_u64(1),
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "ERR out of range (the secret is between 1 and 100)\n\
+                 ERR line longer than {} bytes\n",
+                MAX_LINE
+            )
+        );
+    }
+
+    // plays by bisection over a real socket
+    fn bisect(addr: std::net::SocketAddr, config: Config) -> u32 {
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut replies = BufReader::new(stream.try_clone().unwrap()).lines();
+        let mut stream = stream;
+        let (mut low, mut high) = (config.min, config.max);
+
+        loop {
+            let guess = low + (high - low) / 2;
+            writeln!(stream, "GUESS {}", guess).unwrap();
+
+            let reply = replies.next().unwrap().unwrap();
+            match reply.as_str() {
+                "LOW" => low = guess + 1,
+                "HIGH" => high = guess - 1,
+                _ => {
+                    assert!(reply.starts_with("WIN "), "unexpected reply {}", reply);
+                    return reply[4..].parse().unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn serves_concurrent_clients() {
+        let config = Config::default();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, config));
+
+        let clients: Vec<_> = (0..4)
+            .map(|_| thread::spawn(move || bisect(addr, config)))
+            .collect();
+
+        for client in clients {
+            assert!(client.join().unwrap() <= 7);
+        }
+    }
+}
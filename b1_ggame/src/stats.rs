@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+pub const DEFAULT_FILE: &str = "ggame_stats.json";
+
+// how many entries the leaderboard shows
+const LEADERBOARD_SIZE: usize = 10;
+
+// one finished game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub player: String,
+    pub won: bool,
+    pub attempts: u32,
+    pub min: u32,
+    pub max: u32,
+    pub elapsed_ms: u64,
+}
+
+// win rate and average attempts of a single player
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerStats {
+    pub games: u32,
+    pub wins: u32,
+    // averaged over the games that were won
+    pub average_attempts: Option<f64>,
+}
+
+impl PlayerStats {
+    pub fn win_rate(&self) -> f64 {
+        f64::from(self.wins) / f64::from(self.games)
+    }
+}
+
+// every game played so far, kept in a json file
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub records: Vec<Record>,
+}
+
+impl Stats {
+    // a missing file just means nobody has played yet
+    pub fn load(path: &Path) -> io::Result<Stats> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Stats::default()),
+            Err(e) => return Err(e),
+        };
+
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    // writes to a temporary file first so a crash never leaves a half
+    // written stats file behind
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let tmp = path.with_extension("json.tmp");
+
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(tmp, path)
+    }
+
+    pub fn add(&mut self, record: Record) {
+        self.records.push(record);
+    }
+
+    // the best wins of each --min..=--max range: fewest attempts first, then fastest
+    // games on different ranges aren't ranked together, 3 attempts on 1..=10 is not better
+    // than 7 on 1..=1000
+    pub fn leaderboards(&self) -> BTreeMap<(u32, u32), Vec<&Record>> {
+        let mut boards: BTreeMap<(u32, u32), Vec<&Record>> = BTreeMap::new();
+
+        for r in self.records.iter().filter(|r| r.won) {
+            boards.entry((r.min, r.max)).or_default().push(r);
+        }
+
+        for wins in boards.values_mut() {
+            wins.sort_by(|a, b| {
+                a.attempts
+                    .cmp(&b.attempts)
+                    .then(a.elapsed_ms.cmp(&b.elapsed_ms))
+            });
+            wins.truncate(LEADERBOARD_SIZE);
+        }
+
+        boards
+    }
+
+    pub fn players(&self) -> BTreeMap<&str, PlayerStats> {
+        let mut players: BTreeMap<&str, (u32, u32, u32)> = BTreeMap::new();
+
+        for r in &self.records {
+            let entry = players.entry(&r.player).or_insert((0, 0, 0));
+            entry.0 += 1;
+            if r.won {
+                entry.1 += 1;
+                entry.2 += r.attempts;
+            }
+        }
+
+        players
+            .into_iter()
+            .map(|(name, (games, wins, attempts))| {
+                let average_attempts = if wins > 0 {
+                    Some(f64::from(attempts) / f64::from(wins))
+                } else {
+                    None
+                };
+
+                (
+                    name,
+                    PlayerStats {
+                        games,
+                        wins,
+                        average_attempts,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    // number of games for each attempt count
+    pub fn histogram(&self) -> BTreeMap<u32, u32> {
+        let mut histogram = BTreeMap::new();
+
+        for r in &self.records {
+            *histogram.entry(r.attempts).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+
+    pub fn report<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        if self.records.is_empty() {
+            return writeln!(w, "no games played yet");
+        }
+
+        for ((min, max), wins) in self.leaderboards() {
+            writeln!(w, "leaderboard {}..={}", min, max)?;
+            for (i, r) in wins.iter().enumerate() {
+                writeln!(
+                    w,
+                    "{:>3}. {:<16} {:>3} attempts  {:.1}s",
+                    i + 1,
+                    r.player,
+                    r.attempts,
+                    r.elapsed_ms as f64 / 1000.0
+                )?;
+            }
+            writeln!(w)?;
+        }
+
+        writeln!(w, "players")?;
+        for (name, p) in self.players() {
+            let average = match p.average_attempts {
+                Some(average) => format!("{:.2}", average),
+                None => String::from("-"),
+            };
+
+            writeln!(
+                w,
+                "{:<16} {:>4} games  {:>5.1}% won  {:>6} avg attempts",
+                name,
+                p.games,
+                p.win_rate() * 100.0,
+                average
+            )?;
+        }
+
+        writeln!(w, "\nattempts per game")?;
+        write_histogram(w, &self.histogram())
+    }
+}
+
+// one bar per attempt count, scaled down so the longest bar fits the
+// terminal
+pub fn write_histogram<W: Write>(w: &mut W, histogram: &BTreeMap<u32, u32>) -> io::Result<()> {
+    const WIDTH: u32 = 50;
+
+    let highest = histogram.values().copied().max().unwrap_or(0);
+
+    for (attempts, games) in histogram {
+        let bar = if highest > WIDTH {
+            // round up so a single game still shows up
+            (u64::from(*games) * u64::from(WIDTH)).div_ceil(u64::from(highest)) as usize
+        } else {
+            *games as usize
+        };
+
+        writeln!(w, "{:>4} | {} {}", attempts, "#".repeat(bar), games)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(player: &str, won: bool, attempts: u32, elapsed_ms: u64) -> Record {
+        Record {
+            player: String::from(player),
+            won,
+            attempts,
+            min: 1,
+            max: 100,
+            elapsed_ms,
+        }
+    }
+
+    fn sample() -> Stats {
+        Stats {
+            records: vec![
+                record("anna", true, 7, 9000),
+                record("bob", false, 10, 4000),
+                record("anna", true, 4, 3000),
+                record("bob", true, 4, 2000),
+            ],
+        }
+    }
+
+    #[test]
+    fn leaderboard_ranks_wins_by_attempts_then_time() {
+        let stats = sample();
+        let boards = stats.leaderboards();
+        let board: Vec<(&str, u32)> = boards[&(1, 100)]
+            .iter()
+            .map(|r| (r.player.as_str(), r.attempts))
+            .collect();
+
+        assert_eq!(board, vec![("bob", 4), ("anna", 4), ("anna", 7)]);
+    }
+
+    #[test]
+    fn each_range_has_its_own_leaderboard() {
+        let mut stats = sample();
+        stats.add(Record {
+            min: 1,
+            max: 10,
+            ..record("carl", true, 3, 1000)
+        });
+
+        let boards = stats.leaderboards();
+        assert_eq!(boards.len(), 2);
+        assert_eq!(boards[&(1, 10)].len(), 1);
+        assert_eq!(boards[&(1, 100)][0].player, "bob");
+
+        let mut report = Vec::new();
+        stats.report(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.starts_with("leaderboard 1..=10\n  1. carl"));
+        assert!(report.contains("leaderboard 1..=100\n  1. bob"));
+    }
+
+    #[test]
+    fn player_stats() {
+        let stats = sample();
+        let players = stats.players();
+
+        assert_eq!(players["anna"].win_rate(), 1.0);
+        assert_eq!(players["anna"].average_attempts, Some(5.5));
+        assert_eq!(players["bob"].win_rate(), 0.5);
+        assert_eq!(players["bob"].average_attempts, Some(4.0));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("ggame_stats_{}.json", std::process::id()));
+        let stats = sample();
+
+        stats.save(&path).unwrap();
+        let loaded = Stats::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, stats);
+        assert_eq!(loaded.histogram()[&4], 2);
+    }
+}
@@ -0,0 +1,419 @@
+// the word variant of the game (like mastermind or wordle): the secret is a
+// word from a dictionary file and every guess gets a mark for each letter
+//
+// it's played by the same Game as the numbers, with Words as its rules
+//
+// words are compared letter by letter as unicode chars after lowercasing,
+// so accented letters like à or è count as letters of their own
+// they're put in NFC first: an è typed as e and a combining accent (as some
+// word lists and keyboards do) becomes the single char è
+
+use crate::{Game, Rules};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+
+// a short italian list, next to Cargo.toml
+pub const DEFAULT_DICTIONARY: &str = "words.txt";
+// attempts allowed when --max-attempts is not given
+pub const DEFAULT_ATTEMPTS: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    // right letter in the right place
+    Correct,
+    // the letter is in the secret, but somewhere else
+    Present,
+    Absent,
+}
+
+impl Mark {
+    fn symbol(self) -> char {
+        match self {
+            Mark::Correct => '=',
+            Mark::Present => '+',
+            Mark::Absent => '-',
+        }
+    }
+}
+
+// marks each letter of the guess, a guess of another length than the secret
+// is rejected
+// a letter that shows up more times in the guess than in the secret is only
+// marked as many times as the secret has it, correct places first
+pub fn marks(secret: &[char], guess: &[char]) -> Result<Vec<Mark>, WordRejection> {
+    if secret.len() != guess.len() {
+        return Err(WordRejection::WrongLength {
+            expected: secret.len(),
+            got: guess.len(),
+        });
+    }
+
+    let mut marks = vec![Mark::Absent; guess.len()];
+    // letters of the secret not matched in place yet
+    let mut left: HashMap<char, usize> = HashMap::new();
+
+    for (i, (s, g)) in secret.iter().zip(guess).enumerate() {
+        if s == g {
+            marks[i] = Mark::Correct;
+        } else {
+            *left.entry(*s).or_insert(0) += 1;
+        }
+    }
+
+    for (i, g) in guess.iter().enumerate() {
+        if marks[i] == Mark::Correct {
+            continue;
+        }
+        if let Some(count) = left.get_mut(g) {
+            if *count > 0 {
+                *count -= 1;
+                marks[i] = Mark::Present;
+            }
+        }
+    }
+
+    Ok(marks)
+}
+
+fn normalize(word: &str) -> String {
+    word.trim().nfc().collect::<String>().to_lowercase()
+}
+
+// the words the secret is picked from and guesses are checked against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dictionary {
+    words: BTreeSet<String>,
+}
+
+impl Dictionary {
+    // one word per line, empty lines and lines starting with # are skipped
+    pub fn load(path: &Path) -> io::Result<Dictionary> {
+        let dictionary = Dictionary::from_words(fs::read_to_string(path)?.lines());
+
+        if dictionary.words.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no words in {}", path.display()),
+            ));
+        }
+
+        Ok(dictionary)
+    }
+
+    pub fn from_words<'a, I>(words: I) -> Dictionary
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let words = words
+            .into_iter()
+            .map(normalize)
+            .filter(|word| !word.is_empty() && !word.starts_with('#'))
+            .collect();
+
+        Dictionary { words }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(&normalize(word))
+    }
+
+    pub fn pick<G: Rng>(&self, rng: &mut G) -> Option<&str> {
+        let words: Vec<&String> = self.words.iter().collect();
+
+        words.choose(rng).map(|word| word.as_str())
+    }
+}
+
+// why a line was not taken as a guess
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordRejection {
+    WrongLength { expected: usize, got: usize },
+    NotInDictionary,
+}
+
+impl fmt::Display for WordRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WordRejection::WrongLength { expected, got } => {
+                write!(f, "the word has {} letters, not {}", expected, got)
+            }
+            WordRejection::NotInDictionary => write!(f, "not in the dictionary"),
+        }
+    }
+}
+
+impl std::error::Error for WordRejection {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordOutcome {
+    Miss(Vec<Mark>),
+    // the number of attempts it took to win
+    Win(u32),
+    // out of attempts, with the secret word
+    Lose(String),
+}
+
+// the secret was to be picked from a dictionary without words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyDictionary;
+
+impl fmt::Display for EmptyDictionary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the dictionary has no words")
+    }
+}
+
+impl std::error::Error for EmptyDictionary {}
+
+// the rules of the word game: a secret word and the dictionary the guesses
+// must come from
+pub struct Words {
+    dictionary: Dictionary,
+    secret: Vec<char>,
+    max_attempts: u32,
+    attempts: u32,
+}
+
+// a game with words instead of numbers
+pub type WordGame<R, W> = Game<R, W, Words>;
+
+impl Words {
+    // picks the secret from the dictionary with the given rng
+    pub fn new<G: Rng>(
+        dictionary: Dictionary,
+        max_attempts: u32,
+        rng: &mut G,
+    ) -> Result<Words, EmptyDictionary> {
+        let secret = String::from(dictionary.pick(rng).ok_or(EmptyDictionary)?);
+        Ok(Words::with_secret(dictionary, max_attempts, &secret))
+    }
+
+    pub fn with_secret(dictionary: Dictionary, max_attempts: u32, secret: &str) -> Words {
+        Words {
+            dictionary,
+            secret: normalize(secret).chars().collect(),
+            max_attempts,
+            attempts: 0,
+        }
+    }
+
+    pub fn secret(&self) -> String {
+        self.secret.iter().collect()
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+impl Rules for Words {
+    type Guess = Vec<char>;
+    type Outcome = WordOutcome;
+    type Rejection = WordRejection;
+
+    fn intro<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        writeln!(
+            output,
+            "guess the word of {} letters in {} attempts!",
+            self.secret.len(),
+            self.max_attempts
+        )?;
+        writeln!(output, "= right place, + somewhere else, - not in the word")
+    }
+
+    fn parse_guess(&self, line: &str) -> Result<Vec<char>, WordRejection> {
+        let guess: Vec<char> = normalize(line).chars().collect();
+
+        if guess.len() != self.secret.len() {
+            return Err(WordRejection::WrongLength {
+                expected: self.secret.len(),
+                got: guess.len(),
+            });
+        }
+        if !self.dictionary.contains(line) {
+            return Err(WordRejection::NotInDictionary);
+        }
+
+        Ok(guess)
+    }
+
+    // marks a guess and counts the attempt
+    fn step(&mut self, guess: &Vec<char>) -> Result<WordOutcome, WordRejection> {
+        let marks = marks(&self.secret, guess)?;
+        self.attempts += 1;
+
+        Ok(if marks.iter().all(|mark| *mark == Mark::Correct) {
+            WordOutcome::Win(self.attempts)
+        } else if self.attempts >= self.max_attempts {
+            WordOutcome::Lose(self.secret())
+        } else {
+            WordOutcome::Miss(marks)
+        })
+    }
+
+    fn feedback<W: Write>(
+        &mut self,
+        output: &mut W,
+        guess: &Vec<char>,
+        outcome: &WordOutcome,
+    ) -> io::Result<bool> {
+        match outcome {
+            WordOutcome::Miss(marks) => {
+                let letters: String = guess.iter().map(|c| format!(" {}", c)).collect();
+                let symbols: String = marks.iter().map(|m| format!(" {}", m.symbol())).collect();
+
+                writeln!(output, "{}\n{}", letters, symbols)?;
+                Ok(false)
+            }
+            WordOutcome::Win(_) => {
+                writeln!(output, "YOU WIN")?;
+                Ok(true)
+            }
+            WordOutcome::Lose(secret) => {
+                writeln!(output, "YOU LOSE, the secret word was {}", secret)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Mark::{Absent, Correct, Present};
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    fn marks(secret: &[char], guess: &[char]) -> Vec<Mark> {
+        super::marks(secret, guess).unwrap()
+    }
+
+    #[test]
+    fn marks_places_and_letters() {
+        assert_eq!(
+            marks(&chars("gatto"), &chars("tigre")),
+            vec![Present, Absent, Present, Absent, Absent]
+        );
+        assert_eq!(marks(&chars("casa"), &chars("casa")), vec![Correct; 4]);
+    }
+
+    #[test]
+    fn duplicate_letters_are_only_marked_once_per_occurrence() {
+        // the secret has a single a, matched in place: the other a is absent
+        assert_eq!(
+            marks(&chars("mare"), &chars("mama")),
+            vec![Correct, Correct, Absent, Absent]
+        );
+        // two a in the secret, two misplaced a in the guess
+        assert_eq!(
+            marks(&chars("casa"), &chars("aacs")),
+            vec![Present, Correct, Present, Present]
+        );
+        // only the first of three e can be present
+        assert_eq!(
+            marks(&chars("perla"), &chars("eeeee")),
+            vec![Absent, Correct, Absent, Absent, Absent]
+        );
+    }
+
+    #[test]
+    fn unicode_letters() {
+        assert_eq!(
+            marks(&chars("città"), &chars("àtico")),
+            vec![Present, Present, Present, Present, Absent]
+        );
+
+        let dictionary = Dictionary::from_words(vec!["Perché", "# comment", "", "caffè"]);
+        assert_eq!(dictionary.len(), 2);
+        assert!(dictionary.contains("PERCHÉ\n"));
+        // e and a combining acute accent
+        assert!(dictionary.contains("PERCHE\u{301}"));
+
+        let words = Words::with_secret(dictionary, 6, "perche\u{301}");
+        assert_eq!(words.secret(), "perché");
+        assert_eq!(words.parse_guess("PERCHÉ"), Ok(chars("perché")));
+    }
+
+    #[test]
+    fn words_of_another_length_are_rejected() {
+        let wrong_length = WordRejection::WrongLength {
+            expected: 4,
+            got: 5,
+        };
+        assert_eq!(
+            super::marks(&chars("casa"), &chars("gatto")),
+            Err(wrong_length.clone())
+        );
+
+        let dictionary = Dictionary::from_words(vec!["casa", "gatto"]);
+        let mut words = Words::with_secret(dictionary, 6, "casa");
+        assert_eq!(words.step(&chars("gatto")), Err(wrong_length));
+        assert_eq!(words.attempts(), 0);
+    }
+
+    #[test]
+    fn the_default_dictionary_is_shipped() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_DICTIONARY);
+        let dictionary = Dictionary::load(&path).unwrap();
+
+        assert!(dictionary.len() > 50);
+        assert!(dictionary.contains("città"));
+    }
+
+    #[test]
+    fn empty_dictionary() {
+        let dictionary = Dictionary::from_words(vec!["# nothing", ""]);
+        let words = Words::new(dictionary, 6, &mut rand::thread_rng());
+
+        assert_eq!(words.err(), Some(EmptyDictionary));
+    }
+
+    #[test]
+    fn scripted_game() {
+        let dictionary = Dictionary::from_words(vec!["gatto", "tigre", "città", "caffè"]);
+        let mut output = Vec::new();
+        let mut game = WordGame::with_rules(
+            "cane\ntigre\nlupo?\ncaffè\ncittà\n".as_bytes(),
+            &mut output,
+            Words::with_secret(dictionary, 6, "città"),
+        );
+
+        assert_eq!(game.play().unwrap(), WordOutcome::Win(3));
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("line 1 rejected (\"cane\"): the word has 5 letters, not 4"));
+        assert!(output.contains("line 3 rejected (\"lupo?\"): not in the dictionary"));
+        assert!(output.contains(" c a f f è\n = - - - -\n"));
+    }
+
+    #[test]
+    fn runs_out_of_attempts() {
+        let dictionary = Dictionary::from_words(vec!["uno", "due", "tre"]);
+        let mut words = Words::with_secret(dictionary, 2, "tre");
+
+        assert!(matches!(
+            words.step(&chars("uno")),
+            Ok(WordOutcome::Miss(_))
+        ));
+        assert_eq!(
+            words.step(&chars("due")),
+            Ok(WordOutcome::Lose(String::from("tre")))
+        );
+    }
+}
@@ -33,6 +33,9 @@ fn plus_one(x: i32) -> i32 {
 
 //##### MAIN FUNCTION #####
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     function1();
     function2(5, 6);
     println!("\nfive(): {}", five());
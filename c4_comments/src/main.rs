@@ -1,4 +1,7 @@
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     //comment
 
     //multiline
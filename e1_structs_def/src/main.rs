@@ -12,6 +12,9 @@ struct User {
 }
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     //struct instance creation
     let user1 = User {
         email: String::from("abc@abc.com"),
@@ -5,6 +5,9 @@ struct Rectangle {
 }
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     //let's write a program that calculates the area of a rectangle
     //the program will take the width and height of a rectangle
     //specified in pixels and calculate the area of the rectangle
@@ -37,6 +40,29 @@ fn main() {
         height: 50,
     };
     println!("\nrectx {:#?}", rectx);
+
+    //more shapes than rectangles
+    //area3 only takes a Rectangle, a circle needs another function
+    //the Shape trait of e4_geometry is implemented by rectangles,
+    //circles, triangles and polygons, so a Vec<Box<dyn Shape>> can hold
+    //all of them (see j3_traits_defining_shared_behavior for traits)
+    use e4_geometry::rectangle::Rectangle as Generic;
+    use e4_geometry::scene::Scene;
+    use e4_geometry::shape::{Circle, Point, Triangle};
+
+    let mut scene = Scene::new();
+    scene.add(Generic::new(30u32, 50));
+    scene.add(Generic::square(20u32));
+    scene.add(Circle::new(Point::new(0.0, 0.0), 10.0));
+    scene.add(Triangle::new(
+        Point::new(0.0, 0.0),
+        Point::new(40.0, 0.0),
+        Point::new(0.0, 30.0),
+    ));
+
+    scene.sort_by_area();
+    println!("\nshapes from the smallest {:#?}", scene);
+    println!("total area {:.2}", scene.total_area());
 }
 
 //with variables
@@ -52,6 +52,9 @@ impl Rectangle {
 }
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     //methods are commonly used because of the method syntax
     //the use of methods within our code also improves the structure
     //of our project
@@ -103,4 +106,29 @@ fn main() {
     let rectc = Rectangle::constructor(100, 90);
     println!("\nrectc {:#?}", rectc);
     println!("rectc area: {}", rectc.area());
+
+    //a rectangle of any number type
+    //area multiplies two u32 and the result is a u32 too: with
+    //Rectangle::constructor(70000, 70000) it overflows and the program
+    //panics (in a debug build)
+    //the Rectangle of e4_geometry has a type parameter for its sides
+    //(like Point<T> in j2_generic_data_types) and areas that can't
+    //overflow
+    use e4_geometry::rectangle::Rectangle as Generic;
+
+    let big = Generic::new(70000u32, 70000);
+    println!("\nbig checked area: {:?}", big.checked_area());
+    println!("big saturating area: {}", big.saturating_area());
+    println!("big area as u64: {}", big.area_u64());
+
+    let float = Generic::new(2.5, 4.0);
+    println!("float area: {:?}", float.checked_area());
+
+    //can_hold wants room to spare and doesn't turn the other rectangle:
+    //a 10x40 can't hold a 40x10, but it can contain it rotated
+    let tall = Generic::new(10, 40);
+    let wide = Generic::new(40, 10);
+    println!("\ntall -> wide? {}", tall.can_hold(&wide));
+    println!("tall -> tall? {}", tall.can_contain(&tall));
+    println!("tall -> wide rotated? {}", tall.can_contain_rotated(&wide));
 }
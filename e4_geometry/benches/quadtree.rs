@@ -0,0 +1,127 @@
+// the quadtree against looking at every rectangle of a Vec, with 1000 and 10000 rectangles
+// cargo bench (criterion writes its reports in target/criterion)
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use e4_geometry::quadtree::QuadTree;
+use e4_geometry::rect::Rect;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const WORLD: i32 = 10_000;
+
+// small rectangles scattered over the world, always the same ones
+fn rects(n: usize) -> Vec<Rect> {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    (0..n)
+        .map(|_| {
+            Rect::new(
+                rng.gen_range(0, WORLD),
+                rng.gen_range(0, WORLD),
+                rng.gen_range(1, 100),
+                rng.gen_range(1, 100),
+            )
+        })
+        .collect()
+}
+
+fn tree(rects: &[Rect]) -> QuadTree {
+    let mut tree = QuadTree::new(Rect::new(0, 0, WORLD as u32, WORLD as u32));
+    for rect in rects {
+        tree.insert(*rect);
+    }
+    tree
+}
+
+// the indices of the rectangles that match, in order, like the sorted ids of the tree
+fn scan(rects: &[Rect], matches: impl Fn(&Rect) -> bool) -> Vec<usize> {
+    rects
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| matches(r))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn scan_nearest(rects: &[Rect], x: i32, y: i32) -> Option<usize> {
+    let distance = |r: &Rect| {
+        let dx = (r.left() - i64::from(x))
+            .max(i64::from(x) - (r.right() - 1))
+            .max(0);
+        let dy = (r.top() - i64::from(y))
+            .max(i64::from(y) - (r.bottom() - 1))
+            .max(0);
+        dx * dx + dy * dy
+    };
+
+    (0..rects.len()).min_by_key(|&i| distance(&rects[i]))
+}
+
+fn region_query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("region");
+    let region = Rect::new(4000, 4000, 500, 500);
+
+    for n in [1000, 10_000].iter() {
+        let rects = rects(*n);
+        let tree = tree(&rects);
+
+        group.bench_with_input(BenchmarkId::new("quadtree", n), &tree, |b, tree| {
+            b.iter(|| tree.query(black_box(&region)))
+        });
+        group.bench_with_input(BenchmarkId::new("vec", n), &rects, |b, rects| {
+            b.iter(|| scan(rects, |r| r.overlaps(black_box(&region))))
+        });
+    }
+
+    group.finish();
+}
+
+fn point_query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("point");
+
+    for n in [1000, 10_000].iter() {
+        let rects = rects(*n);
+        let tree = tree(&rects);
+
+        group.bench_with_input(BenchmarkId::new("quadtree", n), &tree, |b, tree| {
+            b.iter(|| tree.at_point(black_box(5000), black_box(5000)))
+        });
+        group.bench_with_input(BenchmarkId::new("vec", n), &rects, |b, rects| {
+            b.iter(|| {
+                scan(rects, |r| {
+                    r.contains_point(black_box(5000), black_box(5000))
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn nearest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nearest");
+
+    for n in [1000, 10_000].iter() {
+        let rects = rects(*n);
+        let tree = tree(&rects);
+
+        group.bench_with_input(BenchmarkId::new("quadtree", n), &tree, |b, tree| {
+            b.iter(|| tree.nearest(black_box(5000), black_box(5000)))
+        });
+        group.bench_with_input(BenchmarkId::new("vec", n), &rects, |b, rects| {
+            b.iter(|| scan_nearest(rects, black_box(5000), black_box(5000)))
+        });
+    }
+
+    group.finish();
+}
+
+// building the tree is the price paid for the faster queries
+fn insert(c: &mut Criterion) {
+    let rects = rects(10_000);
+
+    c.bench_function("insert 10000", |b| b.iter(|| tree(black_box(&rects))));
+}
+
+criterion_group!(benches, region_query, point_query, nearest, insert);
+criterion_main!(benches);
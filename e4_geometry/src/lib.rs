@@ -0,0 +1,18 @@
+// the rectangles of e2_rectangles and e3_method_syntax grown into a small geometry library
+//
+// rect: rectangles with a position, and what can be done with two of them (intersection,
+// union, containment)
+// rectangle: the Rectangle of e3_method_syntax for any number type, with an area that can't
+// overflow and can_hold variants that allow touching edges and rotation
+// shape: a Shape trait for rectangles, circles, triangles and polygons
+// scene: different shapes together in a Vec<Box<dyn Shape>>
+// pack: many small rectangles packed into a container (shelf, guillotine and maxrects)
+// quadtree: finding the rectangles in a region (or at a point, or the nearest one) without
+// looking at all of them
+
+pub mod pack;
+pub mod quadtree;
+pub mod rect;
+pub mod rectangle;
+pub mod scene;
+pub mod shape;
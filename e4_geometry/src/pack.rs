@@ -0,0 +1,538 @@
+// PACKING RECTANGLES INTO A CONTAINER
+
+// can_hold says if one rectangle fits in another, a packer fits many small rectangles (sprites,
+// labels, ...) in one container without overlapping and tells where each one goes
+// finding the best packing takes too long (the problem is NP-hard), so a heuristic places the
+// items one at a time, the biggest first:
+//
+// Shelf: rows as high as their first item, filled from left to right; fast and simple, but
+// items shorter than their row waste the space above them
+// Guillotine: the free space is a list of rectangles, each item goes in the one it fills the
+// most and what's left of it is cut in two with a straight line
+// MaxRects: like Guillotine, but the free rectangles can overlap, so a placement can use space
+// that a guillotine cut would have split; usually the tightest of the three
+//
+// with rotation allowed an item can be turned by 90 degrees when that makes it fit (or fit
+// better)
+
+use crate::rect::Rect;
+use crate::rectangle::Rectangle;
+use std::cmp::Reverse;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    Shelf,
+    Guillotine,
+    MaxRects,
+}
+
+impl Heuristic {
+    pub const ALL: [Heuristic; 3] = [Heuristic::Shelf, Heuristic::Guillotine, Heuristic::MaxRects];
+}
+
+// where an item went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    // the index of the item in the slice given to pack
+    pub item: usize,
+    // the rect has the size of the item turned if rotated is true
+    pub rect: Rect,
+    pub rotated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packing {
+    pub container: Rectangle<u32>,
+    // sorted by item
+    pub placements: Vec<Placement>,
+    // the items that didn't fit, sorted
+    pub unplaced: Vec<usize>,
+}
+
+impl Packing {
+    pub fn used_area(&self) -> u64 {
+        self.placements.iter().map(|p| p.rect.area()).sum()
+    }
+
+    // the area of the container no item is on
+    pub fn wasted_area(&self) -> u64 {
+        self.container.area_u64() - self.used_area()
+    }
+
+    // how much of the container is used, from 0 to 1
+    pub fn occupancy(&self) -> f64 {
+        match self.container.area_u64() {
+            0 => 0.0,
+            area => self.used_area() as f64 / area as f64,
+        }
+    }
+}
+
+// the placements are Rects, so the sides of the container must fit an i32
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainerTooBig {
+    pub container: Rectangle<u32>,
+}
+
+impl fmt::Display for ContainerTooBig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the container is {}x{}, its sides can't be more than {}",
+            self.container.width,
+            self.container.height,
+            i32::MAX
+        )
+    }
+}
+
+impl Error for ContainerTooBig {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packer {
+    container: Rectangle<u32>,
+    heuristic: Heuristic,
+    rotation: bool,
+}
+
+impl Packer {
+    // MaxRects without rotation
+    pub fn new(container: Rectangle<u32>) -> Result<Packer, ContainerTooBig> {
+        if i32::try_from(container.width).is_err() || i32::try_from(container.height).is_err() {
+            return Err(ContainerTooBig { container });
+        }
+
+        Ok(Packer {
+            container,
+            heuristic: Heuristic::MaxRects,
+            rotation: false,
+        })
+    }
+
+    pub fn heuristic(mut self, heuristic: Heuristic) -> Packer {
+        self.heuristic = heuristic;
+        self
+    }
+
+    pub fn allow_rotation(mut self, rotation: bool) -> Packer {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn pack(&self, items: &[Rectangle<u32>]) -> Packing {
+        let mut bin: Box<dyn Bin> = match self.heuristic {
+            Heuristic::Shelf => Box::new(Shelves::new(self.container)),
+            Heuristic::Guillotine => Box::new(Guillotine::new(self.container)),
+            Heuristic::MaxRects => Box::new(MaxRects::new(self.container)),
+        };
+
+        // the longest side first, then the biggest area: small items fill the gaps left by the
+        // big ones, the other way round the big ones often find no room
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by_key(|&i| {
+            let item = items[i];
+            Reverse((item.width.max(item.height), item.area_u64()))
+        });
+
+        let mut placements = Vec::new();
+        let mut unplaced = Vec::new();
+
+        for i in order {
+            let item = items[i];
+
+            let sizes = orientations(item, self.rotation);
+
+            // an item with no area takes no space, it can go anywhere its sides fit
+            if item.area_u64() == 0 {
+                match sizes
+                    .iter()
+                    .find(|(size, _)| self.container.can_contain(size))
+                {
+                    Some(&(size, rotated)) => placements.push(Placement {
+                        item: i,
+                        rect: rect_at(0, 0, size),
+                        rotated,
+                    }),
+                    None => unplaced.push(i),
+                }
+                continue;
+            }
+
+            match bin.insert(&sizes) {
+                Some((rect, rotated)) => placements.push(Placement {
+                    item: i,
+                    rect,
+                    rotated,
+                }),
+                None => unplaced.push(i),
+            }
+        }
+
+        placements.sort_by_key(|p| p.item);
+        unplaced.sort_unstable();
+
+        Packing {
+            container: self.container,
+            placements,
+            unplaced,
+        }
+    }
+}
+
+// the item as it is, and turned if rotation is allowed (and turning changes anything)
+fn orientations(item: Rectangle<u32>, rotation: bool) -> Vec<(Rectangle<u32>, bool)> {
+    let mut sizes = vec![(item, false)];
+    if rotation && item.width != item.height {
+        sizes.push((item.rotated(), true));
+    }
+    sizes
+}
+
+// a rect at (x, y) inside the container, where everything fits an i32
+fn rect_at(x: u32, y: u32, size: Rectangle<u32>) -> Rect {
+    Rect::new(x as i32, y as i32, size.width, size.height)
+}
+
+// a container being filled by one of the heuristics
+trait Bin {
+    // places the item in one of the sizes given, None if none of them fits
+    fn insert(&mut self, sizes: &[(Rectangle<u32>, bool)]) -> Option<(Rect, bool)>;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    // the width taken by the items on the shelf
+    used: u32,
+}
+
+struct Shelves {
+    container: Rectangle<u32>,
+    shelves: Vec<Shelf>,
+}
+
+impl Shelves {
+    fn new(container: Rectangle<u32>) -> Shelves {
+        Shelves {
+            container,
+            shelves: Vec::new(),
+        }
+    }
+}
+
+impl Bin for Shelves {
+    fn insert(&mut self, sizes: &[(Rectangle<u32>, bool)]) -> Option<(Rect, bool)> {
+        // lying flat the item wastes less of the shelf height
+        let mut sizes = sizes.to_vec();
+        sizes.sort_by_key(|(size, _)| size.height);
+
+        for shelf in &mut self.shelves {
+            let room = Rectangle::new(self.container.width - shelf.used, shelf.height);
+
+            if let Some(&(size, rotated)) = sizes.iter().find(|(size, _)| room.can_contain(size)) {
+                let rect = rect_at(shelf.used, shelf.y, size);
+                shelf.used += size.width;
+                return Some((rect, rotated));
+            }
+        }
+
+        let top = self
+            .shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height);
+        let room = Rectangle::new(self.container.width, self.container.height - top);
+        let &(size, rotated) = sizes.iter().find(|(size, _)| room.can_contain(size))?;
+
+        self.shelves.push(Shelf {
+            y: top,
+            height: size.height,
+            used: size.width,
+        });
+        Some((rect_at(0, top, size), rotated))
+    }
+}
+
+// the free rectangle and the size that fit best by score (the lowest wins), if any fits
+fn best_fit<K: Ord>(
+    free: &[Rect],
+    sizes: &[(Rectangle<u32>, bool)],
+    score: impl Fn(&Rect, &Rectangle<u32>) -> K,
+) -> Option<(usize, Rectangle<u32>, bool)> {
+    free.iter()
+        .enumerate()
+        .flat_map(|(i, f)| {
+            sizes
+                .iter()
+                .map(move |&(size, rotated)| (i, f, size, rotated))
+        })
+        .filter(|(_, f, size, _)| f.size().can_contain(size))
+        .min_by_key(|(_, f, size, _)| score(f, size))
+        .map(|(i, _, size, rotated)| (i, size, rotated))
+}
+
+struct Guillotine {
+    free: Vec<Rect>,
+}
+
+impl Guillotine {
+    fn new(container: Rectangle<u32>) -> Guillotine {
+        Guillotine {
+            free: vec![rect_at(0, 0, container)],
+        }
+    }
+}
+
+impl Bin for Guillotine {
+    fn insert(&mut self, sizes: &[(Rectangle<u32>, bool)]) -> Option<(Rect, bool)> {
+        // best area fit: the free rectangle that is left with the least area
+        let (i, size, rotated) = best_fit(&self.free, sizes, |f, size| f.area() - size.area_u64())?;
+
+        let f = self.free.swap_remove(i);
+        let placed = Rect::new(f.x, f.y, size.width, size.height);
+
+        // what's left on the right and below the item, cut along the shorter of the two so the
+        // bigger piece stays as big as possible
+        let right = f.width - size.width;
+        let below = f.height - size.height;
+        let (right, below) = if right < below {
+            (
+                Rect::new(placed.x + size.width as i32, f.y, right, size.height),
+                Rect::new(f.x, placed.y + size.height as i32, f.width, below),
+            )
+        } else {
+            (
+                Rect::new(placed.x + size.width as i32, f.y, right, f.height),
+                Rect::new(f.x, placed.y + size.height as i32, size.width, below),
+            )
+        };
+
+        self.free
+            .extend([right, below].iter().filter(|r| !r.is_empty()));
+        Some((placed, rotated))
+    }
+}
+
+struct MaxRects {
+    free: Vec<Rect>,
+}
+
+impl MaxRects {
+    fn new(container: Rectangle<u32>) -> MaxRects {
+        MaxRects {
+            free: vec![rect_at(0, 0, container)],
+        }
+    }
+}
+
+impl Bin for MaxRects {
+    fn insert(&mut self, sizes: &[(Rectangle<u32>, bool)]) -> Option<(Rect, bool)> {
+        // best short side fit: the free rectangle whose shorter leftover side is the shortest
+        let (i, size, rotated) = best_fit(&self.free, sizes, |f, size| {
+            let right = f.width - size.width;
+            let below = f.height - size.height;
+            (right.min(below), right.max(below))
+        })?;
+
+        let placed = Rect::new(self.free[i].x, self.free[i].y, size.width, size.height);
+
+        // every free rectangle the item is on is replaced by the (up to 4) biggest rectangles
+        // around the item that are still free, which overlap each other
+        let mut free = Vec::with_capacity(self.free.len() + 4);
+        for f in &self.free {
+            if !f.overlaps(&placed) {
+                free.push(*f);
+                continue;
+            }
+
+            let pieces = [
+                Rect::from_edges(f.left(), f.top(), placed.left(), f.bottom()),
+                Rect::from_edges(placed.right(), f.top(), f.right(), f.bottom()),
+                Rect::from_edges(f.left(), f.top(), f.right(), placed.top()),
+                Rect::from_edges(f.left(), placed.bottom(), f.right(), f.bottom()),
+            ];
+            free.extend(pieces.iter().flatten().filter(|piece| !piece.is_empty()));
+        }
+
+        // a free rectangle inside another one adds nothing
+        let mut kept: Vec<Rect> = Vec::with_capacity(free.len());
+        for (i, f) in free.iter().enumerate() {
+            let inside_another = free
+                .iter()
+                .enumerate()
+                .any(|(j, other)| i != j && other.contains(f) && (other != f || j < i));
+            if !inside_another {
+                kept.push(*f);
+            }
+        }
+        self.free = kept;
+
+        Some((placed, rotated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn sizes(sides: &[(u32, u32)]) -> Vec<Rectangle<u32>> {
+        sides.iter().map(|&(w, h)| Rectangle::new(w, h)).collect()
+    }
+
+    #[test]
+    fn a_perfect_fit() {
+        let items = sizes(&[(5, 5), (5, 5), (5, 5), (5, 5)]);
+
+        for heuristic in Heuristic::ALL.iter() {
+            let packing = Packer::new(Rectangle::square(10))
+                .unwrap()
+                .heuristic(*heuristic)
+                .pack(&items);
+
+            assert!(packing.unplaced.is_empty(), "{:?}", heuristic);
+            assert_eq!(packing.wasted_area(), 0);
+            assert_eq!(packing.occupancy(), 1.0);
+        }
+    }
+
+    #[test]
+    fn rotation_makes_room() {
+        let items = sizes(&[(40, 10)]);
+        let packer = Packer::new(Rectangle::new(10, 40)).unwrap();
+
+        for heuristic in Heuristic::ALL.iter() {
+            let packing = packer.heuristic(*heuristic).pack(&items);
+            assert_eq!(packing.unplaced, vec![0]);
+            assert_eq!(packing.wasted_area(), 400);
+
+            let packing = packer
+                .heuristic(*heuristic)
+                .allow_rotation(true)
+                .pack(&items);
+            assert_eq!(
+                packing.placements,
+                vec![Placement {
+                    item: 0,
+                    rect: Rect::new(0, 0, 10, 40),
+                    rotated: true,
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn reports_what_does_not_fit() {
+        let items = sizes(&[(6, 6), (6, 6), (4, 4), (0, 3)]);
+        let packing = Packer::new(Rectangle::square(10))
+            .unwrap()
+            .heuristic(Heuristic::Shelf)
+            .pack(&items);
+
+        assert_eq!(packing.unplaced, vec![1]);
+        assert_eq!(
+            packing
+                .placements
+                .iter()
+                .map(|p| p.item)
+                .collect::<Vec<_>>(),
+            vec![0, 2, 3]
+        );
+        assert_eq!(packing.placements[1].rect, Rect::new(6, 0, 4, 4));
+        assert_eq!(packing.used_area(), 52);
+        assert_eq!(packing.wasted_area(), 48);
+    }
+
+    #[test]
+    fn flat_items_must_fit_too() {
+        let items = sizes(&[(0, 50), (50, 0), (0, 10), (3, 0)]);
+        let packer = Packer::new(Rectangle::square(10)).unwrap();
+
+        let packing = packer.pack(&items);
+        assert_eq!(packing.unplaced, vec![0, 1]);
+        assert_eq!(
+            packing.placements,
+            vec![
+                Placement {
+                    item: 2,
+                    rect: Rect::new(0, 0, 0, 10),
+                    rotated: false,
+                },
+                Placement {
+                    item: 3,
+                    rect: Rect::new(0, 0, 3, 0),
+                    rotated: false,
+                },
+            ]
+        );
+
+        let packing = Packer::new(Rectangle::new(50, 10))
+            .unwrap()
+            .allow_rotation(true)
+            .pack(&items[..1]);
+        assert_eq!(packing.placements[0].rect, Rect::new(0, 0, 50, 0));
+        assert!(packing.placements[0].rotated);
+    }
+
+    #[test]
+    fn the_container_must_fit_an_i32() {
+        let container = Rectangle::new(u32::MAX, 1);
+
+        assert_eq!(Packer::new(container), Err(ContainerTooBig { container }));
+        assert_eq!(
+            ContainerTooBig { container }.to_string(),
+            format!(
+                "the container is {}x1, its sides can't be more than {}",
+                u32::MAX,
+                i32::MAX
+            )
+        );
+    }
+
+    fn items() -> impl Strategy<Value = Vec<Rectangle<u32>>> {
+        prop::collection::vec((0..30u32, 0..30u32), 0..40).prop_map(|sides| {
+            sides
+                .into_iter()
+                .map(|(w, h)| Rectangle::new(w, h))
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn placements_are_inside_and_apart(
+            items in items(),
+            width in 1..100u32,
+            height in 1..100u32,
+            rotation in any::<bool>(),
+        ) {
+            let container = Rect::new(0, 0, width, height);
+
+            for heuristic in Heuristic::ALL.iter() {
+                let packing = Packer::new(container.size())
+                    .unwrap()
+                    .heuristic(*heuristic)
+                    .allow_rotation(rotation)
+                    .pack(&items);
+
+                prop_assert_eq!(packing.placements.len() + packing.unplaced.len(), items.len());
+
+                for (i, p) in packing.placements.iter().enumerate() {
+                    let item = items[p.item];
+                    let size = if p.rotated { item.rotated() } else { item };
+
+                    prop_assert_eq!(p.rect.size(), size);
+                    prop_assert!(container.size().can_contain(&size));
+                    prop_assert!(container.contains(&p.rect));
+                    prop_assert!(rotation || !p.rotated);
+                    for other in &packing.placements[i + 1..] {
+                        prop_assert!(!p.rect.overlaps(&other.rect), "{:?} {:?}", p, other);
+                    }
+                }
+            }
+        }
+    }
+}
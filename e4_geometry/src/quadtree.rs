@@ -0,0 +1,374 @@
+// FINDING RECTANGLES FAST
+
+// "which rectangles overlap this region?" with a Vec<Rect> means looking at every rectangle,
+// which is fine with a hundred of them and slow with thousands, asked many times
+// a quadtree splits the world in 4 quadrants, each quadrant in 4 more and so on, and keeps each
+// rectangle in the smallest quadrant that holds it whole
+// a query only looks into the quadrants that touch its region, so most rectangles are never
+// looked at (benches/quadtree.rs compares it with the Vec)
+//
+// a quadrant is split when it holds more than MAX_ITEMS rectangles; the ones crossing the
+// lines between its children stay where they are
+// rectangles outside the world still work, they're kept in the root and looked at every time
+// empty rectangles are kept too, but no query finds them (they hold no points)
+
+use crate::rect::Rect;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+// rectangles a quadrant holds before it's split
+pub const MAX_ITEMS: usize = 8;
+// quadrants deeper than this are never split (a pile of equal rectangles can't be split apart)
+pub const MAX_DEPTH: usize = 12;
+
+// what insert gives back to refer to the rectangle later (never reused)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Id(usize);
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Rect,
+    depth: usize,
+    items: Vec<Id>,
+    // indexes of the 4 quadrants in QuadTree::nodes
+    children: Option<[usize; 4]>,
+}
+
+impl Node {
+    fn new(bounds: Rect, depth: usize) -> Node {
+        Node {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+}
+
+// the nodes are in a Vec and refer to each other by index, which keeps the borrow checker
+// happy when a node is changed while walking the tree
+#[derive(Debug, Clone)]
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    rects: HashMap<Id, Rect>,
+    next: usize,
+}
+
+impl QuadTree {
+    // world is the area most rectangles are in
+    pub fn new(world: Rect) -> QuadTree {
+        QuadTree {
+            nodes: vec![Node::new(world, 0)],
+            rects: HashMap::new(),
+            next: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn get(&self, id: Id) -> Option<Rect> {
+        self.rects.get(&id).copied()
+    }
+
+    pub fn insert(&mut self, rect: Rect) -> Id {
+        let id = Id(self.next);
+        self.next += 1;
+        self.rects.insert(id, rect);
+
+        let node = self.node_for(&rect);
+        self.nodes[node].items.push(id);
+        if self.nodes[node].items.len() > MAX_ITEMS {
+            self.split(node);
+        }
+
+        id
+    }
+
+    // the rectangle that was removed, None if there's no rectangle with that id
+    // (quadrants that become empty are not merged back, they're reused by the next inserts)
+    pub fn remove(&mut self, id: Id) -> Option<Rect> {
+        let rect = self.rects.remove(&id)?;
+
+        let node = self.node_for(&rect);
+        let items = &mut self.nodes[node].items;
+        if let Some(position) = items.iter().position(|item| *item == id) {
+            items.swap_remove(position);
+        }
+
+        Some(rect)
+    }
+
+    // the rectangles that overlap region, sorted by id
+    pub fn query(&self, region: &Rect) -> Vec<Id> {
+        self.collect(
+            |bounds| bounds.overlaps(region),
+            |rect| rect.overlaps(region),
+        )
+    }
+
+    // the rectangles that contain the point, sorted by id
+    pub fn at_point(&self, x: i32, y: i32) -> Vec<Id> {
+        self.collect(
+            |bounds| bounds.contains_point(x, y),
+            |rect| rect.contains_point(x, y),
+        )
+    }
+
+    // the rectangle closest to the point (one that contains it is at distance 0), the one with
+    // the lowest id if there's a tie
+    // the quadrants are visited from the closest, and a rectangle comes out of the heap only
+    // when no quadrant left can have a closer one
+    pub fn nearest(&self, x: i32, y: i32) -> Option<Id> {
+        // (distance, 0 for a quadrant and 1 for a rectangle, node index or id)
+        // at the same distance the quadrants come first, they may hold a rectangle with a lower id
+        let mut heap = BinaryHeap::new();
+        // the root holds the rectangles outside the world too, it can't be left for later
+        heap.push(Reverse((0, 0, 0)));
+
+        while let Some(Reverse((_, kind, index))) = heap.pop() {
+            if kind == 1 {
+                return Some(Id(index));
+            }
+
+            let node = &self.nodes[index];
+            for id in &node.items {
+                let rect = self.rects[id];
+                if !rect.is_empty() {
+                    heap.push(Reverse((distance(&rect, x, y), 1, id.0)));
+                }
+            }
+            for &child in node.children.iter().flatten() {
+                heap.push(Reverse((
+                    distance(&self.nodes[child].bounds, x, y),
+                    0,
+                    child,
+                )));
+            }
+        }
+
+        None
+    }
+
+    // the deepest node whose bounds hold rect whole (the root if none does)
+    fn node_for(&self, rect: &Rect) -> usize {
+        let mut node = 0;
+
+        while let Some(children) = self.nodes[node].children {
+            match children
+                .iter()
+                .find(|&&child| self.nodes[child].bounds.contains(rect))
+            {
+                Some(&child) => node = child,
+                None => break,
+            }
+        }
+
+        node
+    }
+
+    fn split(&mut self, node: usize) {
+        // a node that has been split already keeps the rectangles that don't fit its children
+        let Node {
+            bounds,
+            depth,
+            children,
+            ..
+        } = self.nodes[node];
+        if children.is_some() || depth >= MAX_DEPTH || bounds.width < 2 || bounds.height < 2 {
+            return;
+        }
+
+        // half of a u32 fits an i32, but a world at the edge of the i32 range has its middle
+        // past it
+        let (half_width, half_height) = (bounds.width / 2, bounds.height / 2);
+        let (middle_x, middle_y) = match (
+            bounds.x.checked_add(half_width as i32),
+            bounds.y.checked_add(half_height as i32),
+        ) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return,
+        };
+        let quadrants = [
+            Rect::new(bounds.x, bounds.y, half_width, half_height),
+            Rect::new(middle_x, bounds.y, bounds.width - half_width, half_height),
+            Rect::new(bounds.x, middle_y, half_width, bounds.height - half_height),
+            Rect::new(
+                middle_x,
+                middle_y,
+                bounds.width - half_width,
+                bounds.height - half_height,
+            ),
+        ];
+
+        let first = self.nodes.len();
+        self.nodes
+            .extend(quadrants.iter().map(|q| Node::new(*q, depth + 1)));
+        self.nodes[node].children = Some([first, first + 1, first + 2, first + 3]);
+
+        // the items that fit a quadrant move down, the others stay
+        let items = std::mem::take(&mut self.nodes[node].items);
+        for id in items {
+            let rect = self.rects[&id];
+            match (first..first + 4).find(|&child| self.nodes[child].bounds.contains(&rect)) {
+                Some(child) => self.nodes[child].items.push(id),
+                None => self.nodes[node].items.push(id),
+            }
+        }
+
+        // everything may have gone in the same quadrant
+        for child in first..first + 4 {
+            if self.nodes[child].items.len() > MAX_ITEMS {
+                self.split(child);
+            }
+        }
+    }
+
+    // walks the nodes whose bounds pass visit and keeps their rectangles that pass keep
+    fn collect<V, K>(&self, visit: V, keep: K) -> Vec<Id>
+    where
+        V: Fn(&Rect) -> bool,
+        K: Fn(&Rect) -> bool,
+    {
+        let mut found = Vec::new();
+        let mut stack = vec![0];
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+
+            found.extend(node.items.iter().filter(|id| keep(&self.rects[*id])));
+            stack.extend(
+                node.children
+                    .iter()
+                    .flatten()
+                    .filter(|&&child| visit(&self.nodes[child].bounds)),
+            );
+        }
+
+        found.sort_unstable();
+        found
+    }
+}
+
+// the squared distance from the point to the closest pixel of a non empty rect
+// (u128 because the difference of two i64 edges squared doesn't fit an i64)
+fn distance(rect: &Rect, x: i32, y: i32) -> u128 {
+    let axis = |p: i64, start: i64, end: i64| {
+        let d = if p < start {
+            start - p
+        } else if p >= end {
+            p - (end - 1)
+        } else {
+            0
+        };
+        (d as u128) * (d as u128)
+    };
+
+    axis(i64::from(x), rect.left(), rect.right()) + axis(i64::from(y), rect.top(), rect.bottom())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn world() -> QuadTree {
+        QuadTree::new(Rect::new(0, 0, 1000, 1000))
+    }
+
+    #[test]
+    fn queries() {
+        let mut tree = world();
+        let a = tree.insert(Rect::new(10, 10, 20, 20));
+        let b = tree.insert(Rect::new(25, 25, 100, 10));
+        let c = tree.insert(Rect::new(900, 900, 50, 50));
+        let outside = tree.insert(Rect::new(-500, -500, 10, 10));
+
+        assert_eq!(tree.query(&Rect::new(0, 0, 30, 30)), vec![a, b]);
+        assert_eq!(tree.query(&Rect::new(-1000, -1000, 3000, 3000)).len(), 4);
+        assert_eq!(tree.at_point(26, 26), vec![a, b]);
+        assert_eq!(tree.at_point(-495, -495), vec![outside]);
+        assert_eq!(tree.nearest(800, 800), Some(c));
+        assert_eq!(tree.nearest(-1000, -1000), Some(outside));
+        assert_eq!(tree.nearest(15, 15), Some(a));
+    }
+
+    #[test]
+    fn insert_and_remove_many() {
+        let mut tree = world();
+        let ids: Vec<Id> = (0..500)
+            .map(|i| tree.insert(Rect::new((i * 37) % 990, (i * 91) % 990, 10, 10)))
+            .collect();
+
+        assert!(tree.nodes.len() > 1);
+        assert_eq!(tree.len(), 500);
+
+        for id in &ids[..250] {
+            assert!(tree.remove(*id).is_some());
+        }
+        assert_eq!(tree.remove(ids[0]), None);
+        assert_eq!(tree.len(), 250);
+        assert_eq!(
+            tree.query(&Rect::new(0, 0, 1000, 1000)),
+            ids[250..].to_vec()
+        );
+        assert_eq!(QuadTree::new(Rect::default()).nearest(0, 0), None);
+    }
+
+    #[test]
+    fn equal_rectangles_do_not_split_forever() {
+        let mut tree = world();
+        for _ in 0..100 {
+            tree.insert(Rect::new(1, 1, 1, 1));
+        }
+
+        assert!(tree.nodes.iter().all(|node| node.depth <= MAX_DEPTH));
+        assert_eq!(tree.at_point(1, 1).len(), 100);
+    }
+
+    fn rects() -> impl Strategy<Value = Vec<Rect>> {
+        prop::collection::vec((-100..1100i32, -100..1100i32, 0..200u32, 0..200u32), 0..200)
+            .prop_map(|rects| {
+                rects
+                    .into_iter()
+                    .map(|(x, y, w, h)| Rect::new(x, y, w, h))
+                    .collect()
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn same_answers_as_a_scan(
+            rects in rects(),
+            region in (-100..1100i32, -100..1100i32, 0..300u32, 0..300u32),
+            removed in 0..50usize,
+        ) {
+            let mut tree = world();
+            let ids: Vec<Id> = rects.iter().map(|r| tree.insert(*r)).collect();
+            for id in ids.iter().take(removed) {
+                tree.remove(*id);
+            }
+            let kept: Vec<(Id, Rect)> = ids.iter().copied().zip(rects.iter().copied()).skip(removed).collect();
+
+            let (x, y, w, h) = region;
+            let region = Rect::new(x, y, w, h);
+            let scan: Vec<Id> = kept.iter().filter(|(_, r)| r.overlaps(&region)).map(|(id, _)| *id).collect();
+            prop_assert_eq!(tree.query(&region), scan);
+
+            let scan: Vec<Id> = kept.iter().filter(|(_, r)| r.contains_point(x, y)).map(|(id, _)| *id).collect();
+            prop_assert_eq!(tree.at_point(x, y), scan);
+
+            let scan = kept
+                .iter()
+                .filter(|(_, r)| !r.is_empty())
+                .min_by_key(|(id, r)| (distance(r, x, y), *id))
+                .map(|(id, _)| *id);
+            prop_assert_eq!(tree.nearest(x, y), scan);
+        }
+    }
+}
@@ -0,0 +1,320 @@
+// RECTANGLES WITH A POSITION
+
+// the Rectangle of e3_method_syntax only has a width and a height, so can_hold can compare two
+// sizes but can't say if two rectangles on a screen overlap
+// a Rect has the position of its top left corner too: x grows to the right and y grows down,
+// like the pixels of a screen
+//
+// x and y are i32 and the sizes are u32, so the right edge (x + width) can be past i32::MAX
+// the edges are computed as i64, where they always fit, and the methods that make a new Rect
+// return None when its position or size doesn't fit the fields (instead of overflowing)
+//
+// the right and bottom edges are not part of the rectangle: a 10x10 Rect at (0, 0) holds the
+// points from (0, 0) to (9, 9), so two rectangles that only touch don't overlap
+// a rectangle with a width or height of 0 is empty: it holds no points and overlaps nothing
+
+use crate::rectangle::Rectangle;
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    // the rectangle from (left, top) to (right, bottom), None if it doesn't fit in a Rect
+    // (or if right < left or bottom < top)
+    pub fn from_edges(left: i64, top: i64, right: i64, bottom: i64) -> Option<Rect> {
+        Some(Rect {
+            x: i32::try_from(left).ok()?,
+            y: i32::try_from(top).ok()?,
+            width: u32::try_from(right.checked_sub(left)?).ok()?,
+            height: u32::try_from(bottom.checked_sub(top)?).ok()?,
+        })
+    }
+
+    pub fn left(&self) -> i64 {
+        i64::from(self.x)
+    }
+
+    pub fn top(&self) -> i64 {
+        i64::from(self.y)
+    }
+
+    pub fn right(&self) -> i64 {
+        self.left() + i64::from(self.width)
+    }
+
+    pub fn bottom(&self) -> i64 {
+        self.top() + i64::from(self.height)
+    }
+
+    // the size without the position
+    pub fn size(&self) -> Rectangle<u32> {
+        Rectangle::new(self.width, self.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    // widened to u64 so the biggest rectangles don't overflow
+    pub fn area(&self) -> u64 {
+        self.size().area_u64()
+    }
+
+    pub fn contains_point(&self, x: i32, y: i32) -> bool {
+        let (x, y) = (i64::from(x), i64::from(y));
+
+        self.left() <= x && x < self.right() && self.top() <= y && y < self.bottom()
+    }
+
+    // true if every point of other is in self (an empty rectangle has no points, so it's in
+    // every rectangle)
+    pub fn contains(&self, other: &Rect) -> bool {
+        other.is_empty()
+            || (self.left() <= other.left()
+                && other.right() <= self.right()
+                && self.top() <= other.top()
+                && other.bottom() <= self.bottom())
+    }
+
+    // true if the two rectangles have at least a point in common
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    // the points in both rectangles, None if there are none
+    // (it's never bigger than either rectangle, so it always fits)
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        Rect::from_edges(
+            self.left().max(other.left()),
+            self.top().max(other.top()),
+            self.right().min(other.right()),
+            self.bottom().min(other.bottom()),
+        )
+    }
+
+    // the smallest rectangle with both rectangles in it (their bounding box), None if it's too
+    // big for a Rect
+    // empty rectangles are left out, they have no points to hold
+    pub fn union(&self, other: &Rect) -> Option<Rect> {
+        if other.is_empty() {
+            return Some(*self);
+        }
+        if self.is_empty() {
+            return Some(*other);
+        }
+
+        Rect::from_edges(
+            self.left().min(other.left()),
+            self.top().min(other.top()),
+            self.right().max(other.right()),
+            self.bottom().max(other.bottom()),
+        )
+    }
+
+    // the same rectangle moved by (dx, dy)
+    pub fn translate(&self, dx: i32, dy: i32) -> Option<Rect> {
+        Some(Rect {
+            x: self.x.checked_add(dx)?,
+            y: self.y.checked_add(dy)?,
+            ..*self
+        })
+    }
+
+    // position and size multiplied by factor (scaling around (0, 0))
+    pub fn scale(&self, factor: u32) -> Option<Rect> {
+        let factor_i32 = i32::try_from(factor).ok();
+
+        Some(Rect {
+            // a factor that doesn't fit an i32 can still scale a position of 0
+            x: if self.x == 0 {
+                0
+            } else {
+                self.x.checked_mul(factor_i32?)?
+            },
+            y: if self.y == 0 {
+                0
+            } else {
+                self.y.checked_mul(factor_i32?)?
+            },
+            width: self.width.checked_mul(factor)?,
+            height: self.height.checked_mul(factor)?,
+        })
+    }
+
+    // moves every edge inwards by amount (outwards if it's negative)
+    // a rectangle can't shrink past nothing: when amount is more than half its size, what's
+    // left is an empty rectangle at its center
+    pub fn inset(&self, amount: i32) -> Option<Rect> {
+        let amount = i64::from(amount);
+
+        let (left, right) = inset_edges(self.left(), self.right(), amount);
+        let (top, bottom) = inset_edges(self.top(), self.bottom(), amount);
+
+        Rect::from_edges(left, top, right, bottom)
+    }
+}
+
+// the two edges of one side moved towards each other, stopping where they meet
+fn inset_edges(start: i64, end: i64, amount: i64) -> (i64, i64) {
+    let (start, end) = (start + amount, end - amount);
+
+    if start <= end {
+        (start, end)
+    } else {
+        let middle = (start + end).div_euclid(2);
+        (middle, middle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn intersection_and_union() {
+        let a = Rect::new(0, 0, 10, 10);
+        let b = Rect::new(5, -5, 10, 10);
+
+        assert_eq!(a.intersection(&b), Some(Rect::new(5, 0, 5, 5)));
+        assert_eq!(a.union(&b), Some(Rect::new(0, -5, 15, 15)));
+
+        // touching is not overlapping
+        let c = Rect::new(10, 0, 5, 5);
+        assert!(!a.overlaps(&c));
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(a.union(&c), Some(Rect::new(0, 0, 15, 10)));
+
+        assert_eq!(a.union(&Rect::new(100, 100, 0, 0)), Some(a));
+    }
+
+    #[test]
+    fn points_and_containment() {
+        let a = Rect::new(-2, -2, 4, 4);
+
+        assert!(a.contains_point(-2, -2));
+        assert!(a.contains_point(1, 1));
+        assert!(!a.contains_point(2, 0));
+        assert!(a.contains(&Rect::new(-1, -1, 3, 3)));
+        assert!(!a.contains(&Rect::new(-1, -1, 4, 3)));
+        assert!(a.contains(&Rect::new(1000, 1000, 0, 5)));
+        assert!(!Rect::new(0, 0, 0, 10).contains_point(0, 0));
+    }
+
+    #[test]
+    fn moving_and_resizing() {
+        let a = Rect::new(2, 3, 10, 20);
+
+        assert_eq!(a.translate(-5, 5), Some(Rect::new(-3, 8, 10, 20)));
+        assert_eq!(a.scale(3), Some(Rect::new(6, 9, 30, 60)));
+        assert_eq!(a.inset(2), Some(Rect::new(4, 5, 6, 16)));
+        assert_eq!(a.inset(-2), Some(Rect::new(0, 1, 14, 24)));
+        assert_eq!(a.inset(6), Some(Rect::new(7, 9, 0, 8)));
+    }
+
+    #[test]
+    fn no_overflow() {
+        let far = Rect::new(i32::MAX - 1, 0, u32::MAX, 1);
+
+        assert_eq!(far.right(), i64::from(i32::MAX) - 1 + i64::from(u32::MAX));
+        assert!(far.contains_point(i32::MAX, 0));
+        assert_eq!(far.translate(2, 0), None);
+        assert_eq!(far.scale(2), None);
+        assert_eq!(far.inset(-2), None);
+        assert_eq!(Rect::new(i32::MIN, 0, u32::MAX, 1).union(&far), None);
+        assert_eq!(
+            Rect::new(0, 0, u32::MAX, u32::MAX).area(),
+            18446744065119617025
+        );
+        assert_eq!(
+            Rect::new(0, 0, 1, 1).scale(u32::MAX),
+            Some(Rect::new(0, 0, u32::MAX, u32::MAX))
+        );
+    }
+
+    // small coordinates make overlapping rectangles likely, big ones test the edges of the types
+    fn rect() -> impl Strategy<Value = Rect> {
+        prop_oneof![
+            (-50..50i32, -50..50i32, 0..50u32, 0..50u32),
+            (any::<i32>(), any::<i32>(), any::<u32>(), any::<u32>()),
+        ]
+        .prop_map(|(x, y, width, height)| Rect::new(x, y, width, height))
+    }
+
+    proptest! {
+        #[test]
+        fn intersection_is_commutative(a in rect(), b in rect()) {
+            prop_assert_eq!(a.intersection(&b), b.intersection(&a));
+            prop_assert_eq!(a.overlaps(&b), b.overlaps(&a));
+        }
+
+        #[test]
+        fn intersection_is_in_both(a in rect(), b in rect()) {
+            match a.intersection(&b) {
+                Some(i) => {
+                    prop_assert!(!i.is_empty());
+                    prop_assert!(a.contains(&i) && b.contains(&i));
+                }
+                None => prop_assert!(!a.overlaps(&b)),
+            }
+        }
+
+        #[test]
+        fn union_contains_both(a in rect(), b in rect()) {
+            if !a.is_empty() && !b.is_empty() {
+                prop_assert_eq!(a.union(&b), b.union(&a));
+            }
+            if let Some(u) = a.union(&b) {
+                prop_assert!(u.contains(&a) && u.contains(&b));
+            }
+        }
+
+        #[test]
+        fn a_point_is_in_the_intersection_if_it_is_in_both(a in rect(), b in rect(), x in -60..60i32, y in -60..60i32) {
+            let in_both = a.contains_point(x, y) && b.contains_point(x, y);
+            let in_intersection = a.intersection(&b).is_some_and(|i| i.contains_point(x, y));
+
+            prop_assert_eq!(in_both, in_intersection);
+        }
+
+        #[test]
+        fn translating_back_gives_the_same_rectangle(a in rect(), dx in -1000..1000i32, dy in -1000..1000i32) {
+            if let Some(moved) = a.translate(dx, dy) {
+                prop_assert_eq!(moved.area(), a.area());
+                prop_assert_eq!(moved.translate(-dx, -dy), Some(a));
+            }
+        }
+
+        #[test]
+        fn an_inset_rectangle_is_inside(a in rect(), amount in 0..100i32) {
+            if let Some(inset) = a.inset(amount) {
+                prop_assert!(inset.area() <= a.area());
+                prop_assert!(a.contains(&inset));
+            }
+        }
+    }
+}
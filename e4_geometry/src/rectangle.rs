@@ -0,0 +1,163 @@
+// A RECTANGLE OF ANY NUMBER TYPE
+
+// the Rectangle of e3_method_syntax with a type parameter for its sides, so the same methods
+// work for u8 pixels, u32 sizes and f64 lengths
+// its area was self.width * self.height, which panics in a debug build (and wraps around in a
+// release build) when the product doesn't fit a u32: 70000 * 70000 is already too much
+// here the area comes in three versions that can't overflow:
+//
+// checked_area: None if the area doesn't fit T
+// saturating_area: the biggest T if the area doesn't fit
+// area_u64: the area as a u64, where the area of any two u32 sides fits
+//
+// the sides are expected not to be negative (the types don't stop a Rectangle<i32> or a
+// Rectangle<f64> from having them)
+
+use std::ops::Mul;
+
+// what a type needs to be the side of a Rectangle
+// implemented for the integer types and the float types below
+pub trait Number: Copy + PartialOrd + Mul<Output = Self> {
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn saturating_mul(self, other: Self) -> Self;
+}
+
+macro_rules! integer {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn checked_mul(self, other: $t) -> Option<$t> {
+                    <$t>::checked_mul(self, other)
+                }
+
+                fn saturating_mul(self, other: $t) -> $t {
+                    <$t>::saturating_mul(self, other)
+                }
+            }
+        )*
+    };
+}
+
+integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+// a float doesn't overflow, it becomes infinite: that's what checked_mul and saturating_mul
+// look for
+macro_rules! float {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn checked_mul(self, other: $t) -> Option<$t> {
+                    Some(self * other).filter(|product| product.is_finite())
+                }
+
+                fn saturating_mul(self, other: $t) -> $t {
+                    (self * other).clamp(<$t>::MIN, <$t>::MAX)
+                }
+            }
+        )*
+    };
+}
+
+float!(f32, f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rectangle<T> {
+    pub width: T,
+    pub height: T,
+}
+
+impl<T> Rectangle<T> {
+    pub fn new(width: T, height: T) -> Rectangle<T> {
+        Rectangle { width, height }
+    }
+
+    // the same rectangle turned by 90 degrees
+    pub fn rotated(self) -> Rectangle<T> {
+        Rectangle {
+            width: self.height,
+            height: self.width,
+        }
+    }
+}
+
+impl<T: Copy> Rectangle<T> {
+    pub fn square(size: T) -> Rectangle<T> {
+        Rectangle {
+            width: size,
+            height: size,
+        }
+    }
+}
+
+impl<T: Number> Rectangle<T> {
+    pub fn checked_area(&self) -> Option<T> {
+        self.width.checked_mul(self.height)
+    }
+
+    pub fn saturating_area(&self) -> T {
+        self.width.saturating_mul(self.height)
+    }
+
+    // the can_hold of e3_method_syntax: other fits with room to spare on both sides
+    pub fn can_hold(&self, other: &Rectangle<T>) -> bool {
+        self.width > other.width && self.height > other.height
+    }
+
+    // other fits, possibly touching the edges (so a rectangle can contain itself)
+    pub fn can_contain(&self, other: &Rectangle<T>) -> bool {
+        self.width >= other.width && self.height >= other.height
+    }
+
+    // other fits as it is or turned by 90 degrees: a 10x40 can't hold a 40x10, but it can
+    // contain it rotated
+    pub fn can_contain_rotated(&self, other: &Rectangle<T>) -> bool {
+        self.can_contain(other) || self.can_contain(&other.rotated())
+    }
+}
+
+impl<T: Copy + Into<u32>> Rectangle<T> {
+    // widening before multiplying means the area always fits: u32::MAX * u32::MAX < u64::MAX
+    pub fn area_u64(&self) -> u64 {
+        u64::from(self.width.into()) * u64::from(self.height.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn areas_that_do_not_overflow() {
+        let big = Rectangle::new(70_000u32, 70_000);
+
+        assert_eq!(big.checked_area(), None);
+        assert_eq!(big.saturating_area(), u32::MAX);
+        assert_eq!(big.area_u64(), 4_900_000_000);
+        assert_eq!(Rectangle::new(30u32, 50).checked_area(), Some(1500));
+        assert_eq!(Rectangle::new(200u8, 2).area_u64(), 400);
+        assert_eq!(Rectangle::new(-3i32, i32::MAX).saturating_area(), i32::MIN);
+    }
+
+    #[test]
+    fn float_areas() {
+        assert_eq!(Rectangle::new(1.5, 2.0).checked_area(), Some(3.0));
+        assert_eq!(Rectangle::new(f64::MAX, 2.0).checked_area(), None);
+        assert_eq!(Rectangle::new(f64::MAX, 2.0).saturating_area(), f64::MAX);
+        assert_eq!(Rectangle::square(0.5f32).checked_area(), Some(0.25));
+    }
+
+    #[test]
+    fn holding_other_rectangles() {
+        let tall = Rectangle::new(10, 40);
+        let wide = Rectangle::new(40, 10);
+
+        assert!(!tall.can_hold(&wide));
+        assert!(!tall.can_contain(&wide));
+        assert!(tall.can_contain_rotated(&wide));
+
+        assert!(!tall.can_hold(&tall));
+        assert!(tall.can_contain(&tall));
+        assert!(Rectangle::new(30, 50).can_hold(&Rectangle::new(10, 40)));
+        assert!(!Rectangle::new(10.0, 40.0).can_contain_rotated(&Rectangle::new(41.0, 5.0)));
+    }
+}
@@ -0,0 +1,109 @@
+// A SCENE OF DIFFERENT SHAPES
+
+// a Vec<Rectangle<u32>> can only hold rectangles, a Vec<Box<dyn Shape>> can hold a circle next
+// to a triangle: each Box points to a value of its own type and calls go through the Shape
+// trait (a trait object)
+// the price is a pointer and a call that can't be inlined for each shape, fine for a scene
+
+use crate::shape::{Bounds, Point, Shape};
+
+#[derive(Debug, Default)]
+pub struct Scene {
+    shapes: Vec<Box<dyn Shape>>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    pub fn add<S: Shape + 'static>(&mut self, shape: S) {
+        self.shapes.push(Box::new(shape));
+    }
+
+    pub fn shapes(&self) -> &[Box<dyn Shape>] {
+        &self.shapes
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    // overlapping shapes are counted once each
+    pub fn total_area(&self) -> f64 {
+        self.shapes.iter().map(|shape| shape.area()).sum()
+    }
+
+    // from the smallest to the largest (f64 isn't Ord, total_cmp gives it an order)
+    pub fn sort_by_area(&mut self) {
+        self.shapes.sort_by(|a, b| a.area().total_cmp(&b.area()));
+    }
+
+    // the bounds of all the shapes, None if there are none
+    pub fn bounding_box(&self) -> Option<Bounds> {
+        self.shapes
+            .iter()
+            .map(|shape| shape.bounding_box())
+            .reduce(|all, bounds| all.union(&bounds))
+    }
+
+    // the shapes p is in
+    pub fn shapes_at<'a>(&'a self, p: &'a Point) -> impl Iterator<Item = &'a dyn Shape> + 'a {
+        self.shapes
+            .iter()
+            .map(|shape| &**shape)
+            .filter(move |shape| shape.contains(p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rectangle::Rectangle;
+    use crate::shape::{Circle, Triangle};
+
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.add(Rectangle::new(4u32, 5));
+        scene.add(Triangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(0.0, 2.0),
+        ));
+        scene.add(Circle::new(Point::new(10.0, 10.0), 1.0));
+        scene.add(Rectangle::square(1.5));
+        scene
+    }
+
+    #[test]
+    fn total_area_and_bounds() {
+        let scene = scene();
+
+        assert_eq!(scene.len(), 4);
+        assert!((scene.total_area() - (20.0 + 2.0 + std::f64::consts::PI + 2.25)).abs() < 1e-12);
+        assert_eq!(
+            scene.bounding_box(),
+            Some(Bounds {
+                min: Point::new(0.0, 0.0),
+                max: Point::new(11.0, 11.0),
+            })
+        );
+        assert_eq!(Scene::new().bounding_box(), None);
+        assert_eq!(scene.shapes_at(&Point::new(1.0, 1.0)).count(), 3);
+    }
+
+    #[test]
+    fn sorting_by_area() {
+        let mut scene = scene();
+        scene.sort_by_area();
+
+        let areas: Vec<f64> = scene.shapes().iter().map(|shape| shape.area()).collect();
+        assert_eq!(areas[0], 2.0);
+        assert_eq!(areas[1], 2.25);
+        assert_eq!(areas[3], 20.0);
+    }
+}
@@ -0,0 +1,482 @@
+// SHAPES
+
+// area, area2 and area3 of e2_rectangles and the methods of e3_method_syntax only know about
+// rectangles
+// Shape is what every shape can do, so code that needs an area or a bounding box can take any
+// of them (and a Vec<Box<dyn Shape>> can hold different ones, see scene.rs)
+//
+// shapes live on a plane with f64 coordinates, y growing down like in rect.rs
+// a Rectangle has no position, so as a Shape its top left corner is at (0, 0)
+// the points on the edge of a shape are in it
+
+use crate::rect::Rect;
+use crate::rectangle::{Number, Rectangle};
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    pub fn distance(&self, other: &Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+// the smallest rectangle with its sides parallel to the axes that has the whole shape in it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    // the bounds of some points, None if there are none
+    pub fn of<'a, I: IntoIterator<Item = &'a Point>>(points: I) -> Option<Bounds> {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+
+        Some(points.fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |bounds, p| Bounds {
+                min: Point::new(bounds.min.x.min(p.x), bounds.min.y.min(p.y)),
+                max: Point::new(bounds.max.x.max(p.x), bounds.max.y.max(p.y)),
+            },
+        ))
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+}
+
+pub trait Shape: fmt::Debug {
+    fn area(&self) -> f64;
+
+    fn perimeter(&self) -> f64;
+
+    fn bounding_box(&self) -> Bounds;
+
+    // true if p is inside the shape or on its edge
+    fn contains(&self, p: &Point) -> bool;
+}
+
+// Rectangle::square is a Rectangle too, so squares are shapes with no extra code
+impl<T: Number + Into<f64> + fmt::Debug> Shape for Rectangle<T> {
+    fn area(&self) -> f64 {
+        self.width.into() * self.height.into()
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * (self.width.into() + self.height.into())
+    }
+
+    fn bounding_box(&self) -> Bounds {
+        Bounds {
+            min: Point::default(),
+            max: Point::new(self.width.into(), self.height.into()),
+        }
+    }
+
+    fn contains(&self, p: &Point) -> bool {
+        self.bounding_box().contains(p)
+    }
+}
+
+// the points of a Rect are whole pixels, as a Shape it covers the whole area from its top left
+// corner to its bottom right one
+impl Shape for Rect {
+    fn area(&self) -> f64 {
+        self.size().area()
+    }
+
+    fn perimeter(&self) -> f64 {
+        self.size().perimeter()
+    }
+
+    fn bounding_box(&self) -> Bounds {
+        Bounds {
+            min: Point::new(self.left() as f64, self.top() as f64),
+            max: Point::new(self.right() as f64, self.bottom() as f64),
+        }
+    }
+
+    fn contains(&self, p: &Point) -> bool {
+        self.bounding_box().contains(p)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: Point, radius: f64) -> Circle {
+        Circle { center, radius }
+    }
+}
+
+impl Shape for Circle {
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * PI * self.radius
+    }
+
+    fn bounding_box(&self) -> Bounds {
+        Bounds {
+            min: Point::new(self.center.x - self.radius, self.center.y - self.radius),
+            max: Point::new(self.center.x + self.radius, self.center.y + self.radius),
+        }
+    }
+
+    fn contains(&self, p: &Point) -> bool {
+        self.center.distance(p) <= self.radius
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub a: Point,
+    pub b: Point,
+    pub c: Point,
+}
+
+impl Triangle {
+    pub fn new(a: Point, b: Point, c: Point) -> Triangle {
+        Triangle { a, b, c }
+    }
+}
+
+impl Shape for Triangle {
+    fn area(&self) -> f64 {
+        shoelace(&[self.a, self.b, self.c]).abs()
+    }
+
+    fn perimeter(&self) -> f64 {
+        perimeter(&[self.a, self.b, self.c])
+    }
+
+    fn bounding_box(&self) -> Bounds {
+        Bounds::of(&[self.a, self.b, self.c]).unwrap()
+    }
+
+    // p is inside if it's on the same side of the three edges (or on one of them)
+    // when the three vertices are on a line every point of that line is on "both sides" of the
+    // edges, so a flat triangle only contains the points of its edges
+    fn contains(&self, p: &Point) -> bool {
+        if shoelace(&[self.a, self.b, self.c]) == 0.0 {
+            return on_segment(&self.a, p, &self.b)
+                || on_segment(&self.b, p, &self.c)
+                || on_segment(&self.c, p, &self.a);
+        }
+
+        let sides = [
+            cross(&self.a, &self.b, p),
+            cross(&self.b, &self.c, p),
+            cross(&self.c, &self.a, p),
+        ];
+
+        sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolygonError {
+    TooFewVertices(usize),
+    // the edges from these vertices cross or overlap
+    SelfIntersecting { first: usize, second: usize },
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolygonError::TooFewVertices(n) => {
+                write!(f, "a polygon needs at least 3 vertices, got {}", n)
+            }
+            PolygonError::SelfIntersecting { first, second } => write!(
+                f,
+                "the edges starting at vertex {} and vertex {} intersect",
+                first, second
+            ),
+        }
+    }
+}
+
+impl Error for PolygonError {}
+
+// a simple polygon: a closed line through the vertices that never crosses itself (the last
+// vertex goes back to the first)
+// the vertices can go clockwise or counterclockwise
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    // checks every pair of edges, so it takes a while with thousands of vertices
+    pub fn new(vertices: Vec<Point>) -> Result<Polygon, PolygonError> {
+        let n = vertices.len();
+        if n < 3 {
+            return Err(PolygonError::TooFewVertices(n));
+        }
+
+        let edge = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+
+        for first in 0..n {
+            for second in first + 1..n {
+                let (p1, p2) = edge(first);
+                let (q1, q2) = edge(second);
+
+                let adjacent = second == first + 1 || (first == 0 && second == n - 1);
+                let bad = if adjacent {
+                    // they share a vertex, so all that can go wrong is folding back onto each other
+                    let (a, shared, b) = if second == first + 1 {
+                        (p1, p2, q2)
+                    } else {
+                        (q1, p1, p2)
+                    };
+                    on_segment(&a, &b, &shared) || on_segment(&shared, &a, &b)
+                } else {
+                    segments_intersect(&p1, &p2, &q1, &q2)
+                };
+
+                if bad {
+                    return Err(PolygonError::SelfIntersecting { first, second });
+                }
+            }
+        }
+
+        Ok(Polygon { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+}
+
+impl Shape for Polygon {
+    fn area(&self) -> f64 {
+        shoelace(&self.vertices).abs()
+    }
+
+    fn perimeter(&self) -> f64 {
+        perimeter(&self.vertices)
+    }
+
+    fn bounding_box(&self) -> Bounds {
+        Bounds::of(&self.vertices).unwrap()
+    }
+
+    // a ray from p to the right crosses the edges an odd number of times if p is inside
+    fn contains(&self, p: &Point) -> bool {
+        let n = self.vertices.len();
+        let mut inside = false;
+
+        for i in 0..n {
+            let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+
+            if on_segment(&a, p, &b) {
+                return true;
+            }
+            if (a.y > p.y) != (b.y > p.y) {
+                let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+                if p.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+
+        inside
+    }
+}
+
+// the shoelace formula: the signed area of the polygon through the points (positive when they
+// go counterclockwise with y growing up)
+fn shoelace(points: &[Point]) -> f64 {
+    let n = points.len();
+    let twice: f64 = (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+
+    twice / 2.0
+}
+
+// the length of the closed line through the points
+fn perimeter(points: &[Point]) -> f64 {
+    let n = points.len();
+
+    (0..n)
+        .map(|i| points[i].distance(&points[(i + 1) % n]))
+        .sum()
+}
+
+// > 0 if p is on one side of the line from a to b, < 0 if it's on the other, 0 if it's on it
+fn cross(a: &Point, b: &Point, p: &Point) -> f64 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+// true if p is on the segment from a to b
+fn on_segment(a: &Point, p: &Point, b: &Point) -> bool {
+    cross(a, b, p) == 0.0
+        && a.x.min(b.x) <= p.x
+        && p.x <= a.x.max(b.x)
+        && a.y.min(b.y) <= p.y
+        && p.y <= a.y.max(b.y)
+}
+
+fn segments_intersect(p1: &Point, p2: &Point, q1: &Point, q2: &Point) -> bool {
+    let d1 = cross(q1, q2, p1);
+    let d2 = cross(q1, q2, p2);
+    let d3 = cross(p1, p2, q1);
+    let d4 = cross(p1, p2, q2);
+
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+
+    on_segment(q1, p1, q2)
+        || on_segment(q1, p2, q2)
+        || on_segment(p1, q1, p2)
+        || on_segment(p1, q2, p2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(coordinates: &[(f64, f64)]) -> Vec<Point> {
+        coordinates.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    #[test]
+    fn rectangles_and_squares() {
+        let r = Rectangle::new(3u32, 4);
+
+        assert_eq!(Shape::area(&r), 12.0);
+        assert_eq!(r.perimeter(), 14.0);
+        assert!(r.contains(&Point::new(3.0, 0.0)));
+        assert!(!r.contains(&Point::new(3.5, 1.0)));
+        assert_eq!(Shape::area(&Rectangle::square(2.5)), 6.25);
+
+        let positioned = Rect::new(-2, 1, 3, 4);
+        assert_eq!(Shape::area(&positioned), 12.0);
+        assert_eq!(
+            positioned.bounding_box(),
+            Bounds {
+                min: Point::new(-2.0, 1.0),
+                max: Point::new(1.0, 5.0),
+            }
+        );
+    }
+
+    #[test]
+    fn circles_and_triangles() {
+        let c = Circle::new(Point::new(1.0, 1.0), 2.0);
+
+        assert!((c.area() - 4.0 * PI).abs() < 1e-12);
+        assert!(c.contains(&Point::new(3.0, 1.0)));
+        assert!(!c.contains(&Point::new(2.5, 2.5)));
+        assert_eq!(c.bounding_box().width(), 4.0);
+
+        let t = Triangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(0.0, 3.0),
+        );
+        assert_eq!(t.area(), 6.0);
+        assert_eq!(t.perimeter(), 12.0);
+        assert!(t.contains(&Point::new(1.0, 1.0)));
+        assert!(t.contains(&Point::new(2.0, 0.0)));
+        assert!(!t.contains(&Point::new(3.0, 3.0)));
+
+        let flat = Triangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+        );
+        assert_eq!(flat.area(), 0.0);
+        assert!(flat.contains(&Point::new(1.5, 1.5)));
+        assert!(flat.contains(&Point::new(0.0, 0.0)));
+        assert!(!flat.contains(&Point::new(10.0, 10.0)));
+        assert!(!flat.contains(&Point::new(-1.0, -1.0)));
+    }
+
+    #[test]
+    fn polygons() {
+        // an L shape, clockwise
+        let l = Polygon::new(points(&[
+            (0.0, 0.0),
+            (0.0, 4.0),
+            (3.0, 4.0),
+            (3.0, 3.0),
+            (1.0, 3.0),
+            (1.0, 0.0),
+        ]))
+        .unwrap();
+
+        assert_eq!(l.area(), 6.0);
+        assert_eq!(l.perimeter(), 14.0);
+        assert!(l.contains(&Point::new(0.5, 1.0)));
+        assert!(l.contains(&Point::new(2.0, 3.5)));
+        assert!(l.contains(&Point::new(1.0, 1.0)));
+        assert!(!l.contains(&Point::new(2.0, 1.0)));
+        assert_eq!(l.bounding_box().height(), 4.0);
+    }
+
+    #[test]
+    fn polygons_must_be_simple() {
+        assert_eq!(
+            Polygon::new(points(&[(0.0, 0.0), (1.0, 1.0)])),
+            Err(PolygonError::TooFewVertices(2))
+        );
+
+        // a bow tie: the first and the third edge cross
+        assert_eq!(
+            Polygon::new(points(&[(0.0, 0.0), (2.0, 2.0), (2.0, 0.0), (0.0, 2.0)])),
+            Err(PolygonError::SelfIntersecting {
+                first: 0,
+                second: 2
+            })
+        );
+
+        // the third vertex goes back along the second edge
+        assert!(Polygon::new(points(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (2.0, 1.0)])).is_err());
+        assert!(Polygon::new(points(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)])).is_err());
+    }
+}
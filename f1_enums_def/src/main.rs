@@ -19,6 +19,9 @@ enum Message {
 }
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     //let's enumerate the possibilities for IP type (v4 and v6)
     //this is a case in which enums are a better choice
     //(instead of structs)
@@ -15,6 +15,9 @@ enum Coin {
 }
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     //match -> extremely powerful control flow operator
     //the match operator allow us to compare a value against a
     //series of patterns and then execute code based on which
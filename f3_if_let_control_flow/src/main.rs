@@ -10,6 +10,9 @@ enum State {
 }
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     //the if let syntax lets you combine if and let into
     //a less verbose way to handle values that match one
     //pattern while ignoring the rest
@@ -5,6 +5,9 @@
 use std::collections::HashMap;
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     let mut map = HashMap::new();
     map.insert(1, 2);
 
@@ -11,6 +11,9 @@
 // program runs
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     // STRING LISTS OF VALUES WITH VECTORS
 
     // Vec<T> is a collection type known as vector
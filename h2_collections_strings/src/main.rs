@@ -1,4 +1,7 @@
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     // STORING UTF-8 ENCODED TEXT WITH STRINGS
 
     // strings are implemented as a collection of bytes plus some methods
@@ -1,4 +1,7 @@
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     // STORING KEYS WITH ASSOCIATED VALUES IN HASH MAPS
 
     // the type HashMap<K,V> stores a mapping of keys of type K to values of type V.
@@ -0,0 +1,3 @@
+// the demo runner of main.rs, in a library so it can be tested
+
+pub mod runner;
@@ -1,4 +1,8 @@
-fn main() {
+fn main() -> std::process::ExitCode {
+    // the panics of this program are reported by the hook of i5_reporting: it prints a short
+    // message and writes the details to a file in crash-reports/ (see below)
+    i5_reporting::install_crash_report!();
+
     // UNRECOVERABLE ERRORS WITH PANIC!
 
     // for unrecoverable errors rust has the panic! macro
@@ -41,9 +45,12 @@ fn main() {
     // let's look at another example to see what it's like when a panic! call comes from a library
     // because of a bug in our code instead of from our code calling the macro directly
 
+    #[allow(clippy::useless_vec)]
     let v = vec![1, 2, 3];
 
-    v[99];
+    // v[99];
+    // (this line ends the program, so it runs in the demo runner at the end of main together
+    // with other panics)
     // this particular situation may cause a buffer overread and can lead to security
     // vulnerabilities in languages like C
     // the C language, will attempt to give you exactly what you asked for in this situation,
@@ -76,4 +83,42 @@ fn main() {
     // problem: line 46 of src/main.rs (the number of the line may be different)
     // the way to fix the panic is to not request an element at index 99 from a vector that only
     // contains 3 items
+
+    // WRITING A CRASH REPORT
+
+    // the first line of main replaces what a panic prints with a panic hook (a function that runs
+    // when a panic starts, before the stack is unwound or the program aborted)
+    // the hook writes the message, the location and a backtrace (even without RUST_BACKTRACE)
+    // to crash-reports/, with the version, the args and a summary of the environment, so the
+    // person who hit the panic only has to send the file
+    // the directory can be changed with the CRASH_REPORT_DIR environment variable
+
+    // RUNNING MANY PANICKING DEMOS
+
+    // a panic ends the program, so v[99] above would be the last thing it does
+    // src/runner.rs runs each demo with std::panic::catch_unwind, which stops a panic before it
+    // ends the program and gives back its payload, then prints how every demo went
+    // (try it with cargo run --release too: the overflow doesn't panic there, it wraps around)
+
+    use i1_unrecoverable_errors_with_panic::runner::Runner;
+
+    let report = Runner::new()
+        .demo("index out of bounds", move || v[99])
+        .demo("overflow", || {
+            let x: u8 = "255".parse().unwrap();
+            x + 1
+        })
+        .demo("unwrap on None", || "".chars().next().unwrap())
+        .demo("explicit panic!", || panic!("crash and burn"))
+        .demo("no panic at all", || "42".parse::<i32>().unwrap())
+        .run();
+
+    // failing to print the report is an error, not a bug, so it's reported (see
+    // i2_recoverable_errors_with_result) instead of turned into a panic with expect
+    let verbose = i5_reporting::report::verbose_requested();
+    i5_reporting::report::capture_backtraces(verbose);
+    let result = report
+        .write(std::io::stdout())
+        .map_err(i5_reporting::report::Report::from);
+    i5_reporting::report::finish(result, verbose)
 }
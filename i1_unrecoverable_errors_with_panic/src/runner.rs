@@ -0,0 +1,163 @@
+// RUNNING PANICKING DEMOS ONE AFTER THE OTHER
+
+// a panic in main ends the program, so a program can only show one panic
+// std::panic::catch_unwind runs a closure and stops the unwinding of a panic in it: it returns
+// Err with the payload of the panic (what was passed to panic!) instead of ending the thread
+// the Runner runs each demo with catch_unwind, remembers how it went and moves on to the next
+//
+// catch_unwind is not a way to handle errors like a try/catch: it doesn't work with
+// panic = 'abort' (the program is gone before there's anything to catch) and the code that
+// panicked may have left its data half updated
+// it's meant for places like this one, or a thread pool, where a panic must not take down
+// everything else
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::panic::{self, UnwindSafe};
+use std::sync::Mutex;
+
+// the panic hook is shared by the whole program, so only one runner at a time swaps it
+static RUNNING: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    // where the hook leaves the location of the last panic of this thread (catch_unwind only
+    // gives back the payload)
+    static LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// what stopped a demo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panic {
+    pub message: String,
+    // file:line:column
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DemoResult {
+    pub name: String,
+    // None if the demo ran to the end
+    pub panic: Option<Panic>,
+}
+
+type Demo = Box<dyn FnOnce() + UnwindSafe>;
+
+#[derive(Default)]
+pub struct Runner {
+    demos: Vec<(String, Demo)>,
+}
+
+impl Runner {
+    pub fn new() -> Runner {
+        Runner::default()
+    }
+
+    // adds a demo, what it returns is thrown away
+    pub fn demo<F, T>(mut self, name: &str, demo: F) -> Runner
+    where
+        F: FnOnce() -> T + UnwindSafe + 'static,
+    {
+        self.demos.push((
+            String::from(name),
+            Box::new(move || {
+                demo();
+            }),
+        ));
+        self
+    }
+
+    // runs the demos in order
+    // while they run, panics don't print anything: the hook that was installed (like the crash
+    // report one) is put back at the end
+    pub fn run(self) -> Report {
+        let _running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = panic::take_hook();
+
+        panic::set_hook(Box::new(|info| {
+            let location = info.location().map(|l| l.to_string());
+            LOCATION.with(|last| *last.borrow_mut() = location);
+        }));
+
+        let results = self
+            .demos
+            .into_iter()
+            .map(|(name, demo)| {
+                let panic = panic::catch_unwind(demo).err().map(|payload| Panic {
+                    message: i5_reporting::crash::message(&*payload),
+                    location: LOCATION.with(|last| last.borrow_mut().take()),
+                });
+
+                DemoResult { name, panic }
+            })
+            .collect();
+
+        panic::set_hook(previous);
+        Report { results }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub results: Vec<DemoResult>,
+}
+
+impl Report {
+    pub fn panicked(&self) -> usize {
+        self.results.iter().filter(|r| r.panic.is_some()).count()
+    }
+
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        for result in &self.results {
+            match &result.panic {
+                None => writeln!(output, "{} ... ok", result.name)?,
+                Some(panic) => writeln!(
+                    output,
+                    "{} ... panicked at {}: {}",
+                    result.name,
+                    panic.location.as_deref().unwrap_or("an unknown location"),
+                    panic.message
+                )?,
+            }
+        }
+
+        writeln!(
+            output,
+            "{} demos, {} panicked",
+            self.results.len(),
+            self.panicked()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_going_after_a_panic() {
+        let report = Runner::new()
+            .demo("index", || Vec::<i32>::new()[0])
+            .demo("fine", || 1 + 1)
+            .demo("explicit", || panic!("crash and burn {}", 42))
+            .run();
+
+        assert_eq!(report.panicked(), 2);
+        assert_eq!(report.results[1].panic, None);
+
+        let panic = report.results[2].panic.as_ref().unwrap();
+        assert_eq!(panic.message, "crash and burn 42");
+        assert!(panic
+            .location
+            .as_ref()
+            .unwrap()
+            .starts_with("src/runner.rs:"));
+
+        let mut output = Vec::new();
+        report.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("index ... panicked at "));
+        assert!(output.contains("fine ... ok\n"));
+        assert!(output.ends_with("3 demos, 2 panicked\n"));
+    }
+}
@@ -0,0 +1,584 @@
+// A CONFIGURATION IN LAYERS
+
+// the read_username_from_file functions read one file whose name is written in the code
+// a real program takes its settings from more than one place, each overriding the one before:
+//
+// 1. the defaults written here
+// 2. a TOML file (a missing file is fine, it's like an empty one)
+// 3. environment variables: APP_ followed by the key in uppercase (APP_USERNAME)
+// 4. command line flags: -- followed by the key with - instead of _ (--username ferris or
+//    --username=ferris)
+//
+// every layer gives text (or a TOML value) for some keys, then each key is parsed and checked
+// once, from the layer that set it last
+// when something is wrong the error says which layer and which key, so the user knows what to
+// fix: "environment: invalid value "ten" for APP_RETRIES"
+//
+// the username can be given as a name (username) or as a file to read it from (username_file,
+// read with read_username_from_file); the one set by the later layer is used, so
+// --username-file on the command line wins over a username in the file (in the same layer a
+// name wins over a file)
+
+use crate::file::OpenPolicy;
+use crate::username::{self, Username};
+use crate::vfs::FileSystem;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const ENV_PREFIX: &str = "APP_";
+
+// every key a layer can set
+pub const KEYS: [&str; 5] = [
+    "username",
+    "username_file",
+    "retries",
+    "backoff_ms",
+    "max_backoff_ms",
+];
+
+// more retries than this is a mistake (with the backoff doubling every time it waits for ages)
+pub const MAX_RETRIES: u32 = 10;
+
+// where a value comes from, in the order the layers are applied (a later layer compares greater)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Default,
+    File(PathBuf),
+    Env,
+    Args,
+}
+
+impl Layer {
+    // how the key is spelled in this layer
+    pub fn key_name(&self, key: &str) -> String {
+        match self {
+            Layer::Default | Layer::File(_) => String::from(key),
+            Layer::Env => format!("{}{}", ENV_PREFIX, key.to_uppercase()),
+            Layer::Args => format!("--{}", key.replace('_', "-")),
+        }
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Layer::Default => write!(f, "defaults"),
+            Layer::File(path) => write!(f, "{}", path.display()),
+            Layer::Env => write!(f, "environment"),
+            Layer::Args => write!(f, "command line"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    // the file is there but can't be read
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // the file is not valid TOML
+    Syntax {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    UnknownKey {
+        layer: Layer,
+        key: String,
+    },
+    // a flag at the end of the command line without its value
+    MissingValue {
+        layer: Layer,
+        key: String,
+    },
+    // the value can't be parsed, or is out of range (source says why)
+    Invalid {
+        layer: Layer,
+        key: String,
+        value: String,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    // neither username nor username_file is set
+    NoUsername,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, .. } => write!(f, "failed to read {}", path.display()),
+            ConfigError::Syntax { path, .. } => write!(f, "{} is not valid TOML", path.display()),
+            ConfigError::UnknownKey { layer, key } => write!(f, "{}: unknown key {}", layer, key),
+            ConfigError::MissingValue { layer, key } => {
+                write!(f, "{}: {} needs a value", layer, key)
+            }
+            ConfigError::Invalid {
+                layer, key, value, ..
+            } => write!(f, "{}: invalid value {:?} for {}", layer, value, key),
+            ConfigError::NoUsername => write!(
+                f,
+                "no username: set username or username_file (or {} or --username)",
+                Layer::Env.key_name("username")
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } => Some(source),
+            ConfigError::Syntax { source, .. } => Some(source),
+            ConfigError::Invalid { source, .. } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+// the error of a value that parses but is not acceptable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutOfRange(pub String);
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for OutOfRange {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub username: Username,
+    pub open_policy: OpenPolicy,
+}
+
+// the layers to read, applied in the order of the methods (whatever order they're called in)
+pub struct Loader<'a, F> {
+    fs: &'a F,
+    file: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    args: Vec<String>,
+}
+
+impl<'a, F: FileSystem> Loader<'a, F> {
+    // the file and the username file are read from fs
+    pub fn new(fs: &'a F) -> Loader<'a, F> {
+        Loader {
+            fs,
+            file: None,
+            env: Vec::new(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn file<P: AsRef<Path>>(mut self, path: P) -> Loader<'a, F> {
+        self.file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    // all the variables (like std::env::vars()), the ones without APP_ in front are ignored
+    pub fn env<I: IntoIterator<Item = (String, String)>>(mut self, vars: I) -> Loader<'a, F> {
+        self.env = vars.into_iter().collect();
+        self
+    }
+
+    // the arguments without the program name
+    pub fn args<I: IntoIterator<Item = String>>(mut self, args: I) -> Loader<'a, F> {
+        self.args = args.into_iter().collect();
+        self
+    }
+
+    pub fn load(&self) -> Result<Config, ConfigError> {
+        let mut values = Values::default();
+
+        if let Some(path) = &self.file {
+            values.read_file(self.fs, path)?;
+        }
+        values.read_env(&self.env);
+        values.read_args(&self.args)?;
+
+        values.config(self.fs)
+    }
+}
+
+// the text of each key and the layer it comes from, the last layer that sets a key wins
+#[derive(Debug, Default)]
+struct Values {
+    values: HashMap<&'static str, (Layer, String)>,
+}
+
+impl Values {
+    fn set(&mut self, layer: Layer, key: &str, value: String) -> Result<(), ConfigError> {
+        match KEYS.iter().find(|k| **k == key) {
+            Some(key) => {
+                self.values.insert(key, (layer, value));
+                Ok(())
+            }
+            None => Err(ConfigError::UnknownKey {
+                key: layer.key_name(key),
+                layer,
+            }),
+        }
+    }
+
+    fn read_file<F: FileSystem>(&mut self, fs: &F, path: &Path) -> Result<(), ConfigError> {
+        let read_error = |source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        let mut file = match fs.open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(read_error(e)),
+        };
+        let mut text = String::new();
+        file.read_to_string(&mut text).map_err(read_error)?;
+
+        let table: toml::Table = text.parse().map_err(|source| ConfigError::Syntax {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let layer = Layer::File(path.to_path_buf());
+        for (key, value) in table {
+            // strings are taken as they are, numbers and booleans are turned into text so every
+            // layer is parsed the same way
+            let text = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Integer(_) | toml::Value::Boolean(_) => value.to_string(),
+                other => {
+                    return Err(ConfigError::Invalid {
+                        layer,
+                        value: other.to_string(),
+                        key,
+                        source: Box::new(OutOfRange(format!(
+                            "expected a string or a number, not {}",
+                            other.type_str()
+                        ))),
+                    })
+                }
+            };
+            self.set(layer.clone(), &key, text)?;
+        }
+
+        Ok(())
+    }
+
+    // other programs may use APP_ variables too, so the unknown ones are not an error here
+    fn read_env(&mut self, vars: &[(String, String)]) {
+        for (name, value) in vars {
+            if let Some(key) = name.strip_prefix(ENV_PREFIX) {
+                let _ = self.set(Layer::Env, &key.to_lowercase(), value.clone());
+            }
+        }
+    }
+
+    fn read_args(&mut self, args: &[String]) -> Result<(), ConfigError> {
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let flag = match arg.strip_prefix("--") {
+                Some(flag) => flag,
+                None => {
+                    return Err(ConfigError::UnknownKey {
+                        layer: Layer::Args,
+                        key: arg.clone(),
+                    })
+                }
+            };
+
+            let (key, value) = match flag.split_once('=') {
+                Some((key, value)) => (key, value.to_string()),
+                None => match args.next() {
+                    Some(value) => (flag, value.clone()),
+                    None => {
+                        return Err(ConfigError::MissingValue {
+                            layer: Layer::Args,
+                            key: arg.clone(),
+                        })
+                    }
+                },
+            };
+
+            self.set(Layer::Args, &key.replace('-', "_"), value)?;
+        }
+
+        Ok(())
+    }
+
+    // parses the value of key, or gives default if no layer set it
+    fn get<T>(&self, key: &str, default: T) -> Result<(Layer, T), ConfigError>
+    where
+        T: FromStr,
+        T::Err: Error + Send + Sync + 'static,
+    {
+        match self.values.get(key) {
+            Some((layer, value)) => value
+                .parse()
+                .map(|parsed| (layer.clone(), parsed))
+                .map_err(|e| invalid(layer, key, value, e)),
+            None => Ok((Layer::Default, default)),
+        }
+    }
+
+    fn config<F: FileSystem>(&self, fs: &F) -> Result<Config, ConfigError> {
+        let name = self.values.get("username");
+        let file = self.values.get("username_file");
+
+        let from_file = |layer: &Layer, path: &String| {
+            username::read_username_from_file(fs, path)
+                .map_err(|e| invalid(layer, "username_file", path, e))
+        };
+
+        let username = match (name, file) {
+            (Some((name_layer, _)), Some((layer, path))) if layer > name_layer => {
+                from_file(layer, path)?
+            }
+            (Some((layer, name)), _) => {
+                Username::new(name).map_err(|e| invalid(layer, "username", name, e))?
+            }
+            (None, Some((layer, path))) => from_file(layer, path)?,
+            (None, None) => return Err(ConfigError::NoUsername),
+        };
+
+        let defaults = OpenPolicy::default();
+        let (layer, retries) = self.get("retries", defaults.retries)?;
+        if retries > MAX_RETRIES {
+            return Err(out_of_range(
+                &layer,
+                "retries",
+                retries,
+                format!("at most {} retries", MAX_RETRIES),
+            ));
+        }
+
+        let (_, backoff) = self.get("backoff_ms", defaults.backoff.as_millis() as u64)?;
+        let (layer, max_backoff) =
+            self.get("max_backoff_ms", defaults.max_backoff.as_millis() as u64)?;
+        if max_backoff < backoff {
+            return Err(out_of_range(
+                &layer,
+                "max_backoff_ms",
+                max_backoff,
+                format!("must not be less than backoff_ms ({})", backoff),
+            ));
+        }
+
+        Ok(Config {
+            username,
+            open_policy: OpenPolicy {
+                retries,
+                backoff: Duration::from_millis(backoff),
+                max_backoff: Duration::from_millis(max_backoff),
+            },
+        })
+    }
+}
+
+fn invalid<E>(layer: &Layer, key: &str, value: &str, source: E) -> ConfigError
+where
+    E: Error + Send + Sync + 'static,
+{
+    ConfigError::Invalid {
+        layer: layer.clone(),
+        key: layer.key_name(key),
+        value: String::from(value),
+        source: Box::new(source),
+    }
+}
+
+fn out_of_range<T: fmt::Display>(
+    layer: &Layer,
+    key: &str,
+    value: T,
+    reason: String,
+) -> ConfigError {
+    invalid(layer, key, &value.to_string(), OutOfRange(reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::username::UsernameError;
+    use crate::vfs::{Fault, MemoryFs};
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (String::from(*name), String::from(*value)))
+            .collect()
+    }
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    fn fs_with_config(text: &str) -> MemoryFs {
+        let fs = MemoryFs::new();
+        fs.add_file("app.toml", text.as_bytes());
+        fs
+    }
+
+    #[test]
+    fn later_layers_win() {
+        let fs = fs_with_config("username = \"file\"\nretries = 1\nbackoff_ms = 5\n");
+
+        let loader = Loader::new(&fs).file("app.toml");
+        let config = loader.load().unwrap();
+        assert_eq!(config.username.as_str(), "file");
+        assert_eq!(config.open_policy.retries, 1);
+        assert_eq!(config.open_policy.backoff, Duration::from_millis(5));
+        assert_eq!(config.open_policy.max_backoff, Duration::from_secs(1));
+
+        let loader = loader.env(env(&[
+            ("APP_USERNAME", "env"),
+            ("APP_RETRIES", "2"),
+            ("HOME", "/root"),
+        ]));
+        let config = loader.load().unwrap();
+        assert_eq!(config.username.as_str(), "env");
+        assert_eq!(config.open_policy.retries, 2);
+
+        let config = loader
+            .args(args("--username args --max-backoff-ms=50"))
+            .load()
+            .unwrap();
+        assert_eq!(config.username.as_str(), "args");
+        assert_eq!(config.open_policy.retries, 2);
+        assert_eq!(config.open_policy.max_backoff, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn username_from_a_file() {
+        let fs = fs_with_config("username_file = \"hello4.txt\"\n");
+        fs.add_file("hello4.txt", b"ferris\n");
+
+        let loader = Loader::new(&fs).file("app.toml");
+        assert_eq!(loader.load().unwrap().username.as_str(), "ferris");
+
+        // a name in a later layer wins over the file
+        let config = loader.env(env(&[("APP_USERNAME", "crab")])).load().unwrap();
+        assert_eq!(config.username.as_str(), "crab");
+
+        fs.inject("hello4.txt", Fault::PermissionDenied);
+        let e = Loader::new(&fs).file("app.toml").load().unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "app.toml: invalid value \"hello4.txt\" for username_file"
+        );
+        assert!(e.source().unwrap().is::<UsernameError>());
+    }
+
+    #[test]
+    fn a_username_file_in_a_later_layer_wins() {
+        let fs = fs_with_config("username = \"file\"\n");
+        fs.add_file("name.txt", b"ferris\n");
+
+        let config = Loader::new(&fs)
+            .file("app.toml")
+            .args(args("--username-file name.txt"))
+            .load()
+            .unwrap();
+        assert_eq!(config.username.as_str(), "ferris");
+
+        // in the same layer the name wins
+        let config = Loader::new(&fs)
+            .args(args("--username-file name.txt --username crab"))
+            .load()
+            .unwrap();
+        assert_eq!(config.username.as_str(), "crab");
+    }
+
+    #[test]
+    fn a_missing_file_is_an_empty_layer() {
+        let fs = MemoryFs::new();
+        let loader = Loader::new(&fs).file("app.toml");
+
+        assert!(matches!(loader.load(), Err(ConfigError::NoUsername)));
+        assert_eq!(
+            loader.args(args("--username ferris")).load().unwrap(),
+            Config {
+                username: Username::new("ferris").unwrap(),
+                open_policy: OpenPolicy::default(),
+            }
+        );
+
+        fs.add_file("app.toml", b"");
+        fs.inject("app.toml", Fault::PermissionDenied);
+        let e = Loader::new(&fs).file("app.toml").load().unwrap_err();
+        assert!(matches!(e, ConfigError::Read { .. }));
+    }
+
+    #[test]
+    fn errors_name_the_layer_and_the_key() {
+        let fs = fs_with_config("username = \"ferris\"\nretries = \"many\"\n");
+        let loader = Loader::new(&fs).file("app.toml");
+        assert_eq!(
+            loader.load().unwrap_err().to_string(),
+            "app.toml: invalid value \"many\" for retries"
+        );
+
+        let fs = fs_with_config("username = \"ferris\"\n");
+        let loader = Loader::new(&fs).file("app.toml");
+        let e = loader
+            .env(env(&[("APP_RETRIES", "ten")]))
+            .load()
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "environment: invalid value \"ten\" for APP_RETRIES"
+        );
+
+        let load = |s: &str| {
+            Loader::new(&fs)
+                .file("app.toml")
+                .args(args(s))
+                .load()
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            load("--retries 11"),
+            "command line: invalid value \"11\" for --retries"
+        );
+        assert_eq!(
+            load("--backoff-ms 10 --max-backoff-ms 5"),
+            "command line: invalid value \"5\" for --max-backoff-ms"
+        );
+        assert_eq!(
+            load("--username fer/ris"),
+            "command line: invalid value \"fer/ris\" for --username"
+        );
+        assert_eq!(load("--colour red"), "command line: unknown key --colour");
+        assert_eq!(load("--retries"), "command line: --retries needs a value");
+    }
+
+    #[test]
+    fn bad_files() {
+        let e = Loader::new(&fs_with_config("username = "))
+            .file("app.toml")
+            .load()
+            .unwrap_err();
+        assert!(matches!(e, ConfigError::Syntax { .. }));
+
+        let e = Loader::new(&fs_with_config("name = \"ferris\""))
+            .file("app.toml")
+            .load()
+            .unwrap_err();
+        assert_eq!(e.to_string(), "app.toml: unknown key name");
+
+        let e = Loader::new(&fs_with_config("retries = [1, 2]"))
+            .file("app.toml")
+            .load()
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "app.toml: invalid value \"[1, 2]\" for retries"
+        );
+    }
+}
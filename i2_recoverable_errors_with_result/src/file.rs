@@ -0,0 +1,442 @@
+// OPENING OR CREATING A FILE WITHOUT PANICKING
+
+// main.rs opens hello.txt, creates it if it doesn't exist and panics on any other error, once
+// with match and once with unwrap_or_else
+// here the same thing is a function that returns the error instead, with what it was doing and
+// on which path, so the calling code decides what to do with it
+// the filesystem is a parameter (see vfs.rs), so the tests can make it fail in every way
+// some errors are worth another try: Interrupted (a signal arrived during the call) and
+// WouldBlock (the file is busy, on a non-blocking handle) usually go away by themselves, so
+// those are retried a few times, waiting a bit longer each time
+
+use crate::vfs::FileSystem;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+// how hard open_or_create tries before giving up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenPolicy {
+    // attempts after the first one, for errors that are worth retrying
+    pub retries: u32,
+    // the wait before the first retry, doubled after each one
+    pub backoff: Duration,
+    // the longest wait between two attempts
+    pub max_backoff: Duration,
+}
+
+impl OpenPolicy {
+    // a single attempt
+    pub fn no_retries() -> OpenPolicy {
+        OpenPolicy {
+            retries: 0,
+            ..OpenPolicy::default()
+        }
+    }
+
+    // the wait before the given retry (counting from 0)
+    fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.checked_pow(retry).unwrap_or(u32::MAX);
+
+        self.backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |delay| delay.min(self.max_backoff))
+    }
+}
+
+impl Default for OpenPolicy {
+    fn default() -> OpenPolicy {
+        OpenPolicy {
+            retries: 3,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+// the errors worth another attempt
+fn is_transient(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::Interrupted | ErrorKind::WouldBlock)
+}
+
+#[derive(Debug)]
+pub enum FileError {
+    Open {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Create {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // every attempt failed with an error worth retrying, source is the last one
+    GaveUp {
+        path: PathBuf,
+        attempts: u32,
+        source: io::Error,
+    },
+    Write {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+        source: io::Error,
+    },
+}
+
+impl FileError {
+    // the io error this one was made from
+    pub fn io_error(&self) -> &io::Error {
+        match self {
+            FileError::Open { source, .. }
+            | FileError::Create { source, .. }
+            | FileError::GaveUp { source, .. }
+            | FileError::Write { source, .. }
+            | FileError::Rename { source, .. } => source,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.io_error().kind()
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileError::Open { path, .. } => write!(f, "problem opening {}", path.display()),
+            FileError::Create { path, .. } => write!(f, "problem creating {}", path.display()),
+            FileError::GaveUp { path, attempts, .. } => write!(
+                f,
+                "gave up on {} after {} attempts",
+                path.display(),
+                attempts
+            ),
+            FileError::Write { path, .. } => write!(f, "problem writing {}", path.display()),
+            FileError::Rename { from, to, .. } => {
+                write!(f, "problem renaming {} to {}", from.display(), to.display())
+            }
+        }
+    }
+}
+
+impl Error for FileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.io_error())
+    }
+}
+
+// runs op until it succeeds, fails with an error not worth retrying, or runs out of retries
+// returns the number of attempts with the error
+fn retry<T, F>(policy: &OpenPolicy, mut op: F) -> Result<T, (u32, io::Error)>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if is_transient(&e) && attempts <= policy.retries => {
+                thread::sleep(policy.delay(attempts - 1));
+            }
+            Err(e) => return Err((attempts, e)),
+        }
+    }
+}
+
+// a file opened by open_or_create
+#[derive(Debug)]
+pub struct Opened<T> {
+    pub file: T,
+    // true if the file didn't exist and was created empty
+    pub created: bool,
+}
+
+// opens path for reading and writing, and creates it if it doesn't exist
+pub fn open_or_create<F, P>(
+    fs: &F,
+    path: P,
+    policy: OpenPolicy,
+) -> Result<Opened<F::File>, FileError>
+where
+    F: FileSystem,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let error = |attempts: u32, source: io::Error, create: bool| {
+        let path = path.to_path_buf();
+
+        if is_transient(&source) {
+            FileError::GaveUp {
+                path,
+                attempts,
+                source,
+            }
+        } else if create {
+            FileError::Create { path, source }
+        } else {
+            FileError::Open { path, source }
+        }
+    };
+
+    // the file can show up between open and create_new (then it's opened again instead of
+    // being truncated like File::create would do), but a dangling symlink is not found by open
+    // and already exists for create_new every time: the rounds are limited by policy.retries
+    let mut rounds = 0;
+
+    loop {
+        rounds += 1;
+
+        match retry(&policy, || fs.open_read_write(path)) {
+            Ok(file) => {
+                return Ok(Opened {
+                    file,
+                    created: false,
+                })
+            }
+            Err((_, e)) if e.kind() == ErrorKind::NotFound => {}
+            Err((attempts, e)) => return Err(error(attempts, e, false)),
+        }
+
+        match retry(&policy, || fs.create_new(path)) {
+            Ok(file) => {
+                return Ok(Opened {
+                    file,
+                    created: true,
+                })
+            }
+            Err((_, e)) if e.kind() == ErrorKind::AlreadyExists && rounds <= policy.retries => {
+                continue
+            }
+            Err((_, e)) if e.kind() == ErrorKind::AlreadyExists => {
+                return Err(FileError::GaveUp {
+                    path: path.to_path_buf(),
+                    attempts: rounds,
+                    source: e,
+                })
+            }
+            Err((attempts, e)) => return Err(error(attempts, e, true)),
+        }
+    }
+}
+
+// writes contents to a temporary file next to path and then renames it to path
+// a rename on the same filesystem replaces the file in one step, so whoever reads path sees
+// either the old contents or the new ones, never half of them (even if the program crashes
+// while writing)
+pub fn write_atomic<F, P>(fs: &F, path: P, contents: &[u8]) -> Result<(), FileError>
+where
+    F: FileSystem,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let tmp = temp_path(path);
+
+    let written = fs.create(&tmp).and_then(|mut file| {
+        file.write_all(contents)?;
+        fs.sync(&mut file)
+    });
+
+    if let Err(source) = written {
+        let _ = fs.remove_file(&tmp);
+        return Err(FileError::Write { path: tmp, source });
+    }
+
+    if let Err(source) = fs.rename(&tmp, path) {
+        let _ = fs.remove_file(&tmp);
+        return Err(FileError::Rename {
+            from: tmp,
+            to: path.to_path_buf(),
+            source,
+        });
+    }
+
+    Ok(())
+}
+
+// .name.tmp.pid in the same directory (the rename only replaces the file in one step if both
+// are on the same filesystem)
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    path.with_file_name(format!(".{}.tmp.{}", name, std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::{Fault, MemoryFs, RealFs};
+    use std::fs;
+    use std::io::Read;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("i2_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_limit() {
+        let policy = OpenPolicy {
+            retries: 10,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(3), Duration::from_millis(500));
+        assert_eq!(policy.delay(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn retries_only_transient_errors() {
+        let policy = OpenPolicy {
+            retries: 2,
+            backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        };
+
+        let mut calls = 0;
+        let result = retry(&policy, || {
+            calls += 1;
+            if calls < 3 {
+                Err(io::Error::from(ErrorKind::Interrupted))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let (attempts, e) = retry(&policy, || -> io::Result<()> {
+            Err(io::Error::from(ErrorKind::WouldBlock))
+        })
+        .unwrap_err();
+        assert_eq!((attempts, e.kind()), (3, ErrorKind::WouldBlock));
+
+        let (attempts, _) = retry(&policy, || -> io::Result<()> {
+            Err(io::Error::from(ErrorKind::PermissionDenied))
+        })
+        .unwrap_err();
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn opens_or_creates() {
+        let dir = temp_dir("open_or_create");
+        let path = dir.join("hello.txt");
+
+        let first = open_or_create(&RealFs, &path, OpenPolicy::default()).unwrap();
+        assert!(first.created);
+        fs::write(&path, "hello").unwrap();
+
+        let mut second = open_or_create(&RealFs, &path, OpenPolicy::default()).unwrap();
+        let mut contents = String::new();
+        second.file.read_to_string(&mut contents).unwrap();
+        assert!(!second.created);
+        assert_eq!(contents, "hello");
+
+        let missing = dir.join("missing/hello.txt");
+        let err = open_or_create(&RealFs, missing, OpenPolicy::no_retries()).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(err, FileError::Create { .. }));
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn atomic_write_replaces_the_file() {
+        let dir = temp_dir("write_atomic");
+        let path = dir.join("hello.txt");
+
+        write_atomic(&RealFs, &path, b"old").unwrap();
+        write_atomic(&RealFs, &path, b"new").unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let leftovers = fs::read_dir(&dir).unwrap().count();
+
+        let err = write_atomic(&RealFs, dir.join("missing/hello.txt"), b"x").unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(contents, "new");
+        assert_eq!(leftovers, 1);
+        assert!(matches!(err, FileError::Write { .. }));
+    }
+
+    fn quick() -> OpenPolicy {
+        OpenPolicy {
+            retries: 3,
+            backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn other_errors_are_returned() {
+        let fs = MemoryFs::new();
+        fs.add_file("hello.txt", b"hello");
+        fs.inject("hello.txt", Fault::PermissionDenied);
+
+        let err = open_or_create(&fs, "hello.txt", quick()).unwrap_err();
+        assert!(matches!(err, FileError::Open { .. }));
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(err.to_string(), "problem opening hello.txt");
+
+        fs.inject("new.txt", Fault::PermissionDenied);
+        let err = open_or_create(&fs, "new.txt", quick()).unwrap_err();
+        assert!(matches!(err, FileError::Open { .. }));
+    }
+
+    #[test]
+    fn transient_errors_are_retried() {
+        let fs = MemoryFs::new();
+        fs.add_file("busy.txt", b"hello");
+
+        fs.inject("busy.txt", Fault::Interrupted(3));
+        let opened = open_or_create(&fs, "busy.txt", quick()).unwrap();
+        assert!(!opened.created);
+
+        fs.inject("busy.txt", Fault::Interrupted(10));
+        match open_or_create(&fs, "busy.txt", quick()) {
+            Err(FileError::GaveUp { attempts: 4, .. }) => {}
+            other => panic!("expected to give up after 4 attempts, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dangling_links_are_not_retried_forever() {
+        let fs = MemoryFs::new();
+        fs.inject("link.txt", Fault::DanglingLink);
+
+        let err = open_or_create(&fs, "link.txt", quick()).unwrap_err();
+        assert!(matches!(err, FileError::GaveUp { attempts: 4, .. }));
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+
+        let err = open_or_create(&fs, "link.txt", OpenPolicy::no_retries()).unwrap_err();
+        assert!(matches!(err, FileError::GaveUp { attempts: 1, .. }));
+    }
+
+    #[test]
+    fn failed_atomic_write_leaves_the_old_file() {
+        let fs = MemoryFs::new();
+        fs.add_file("hello.txt", b"old");
+        fs.inject("hello.txt", Fault::PermissionDenied);
+
+        let err = write_atomic(&fs, "hello.txt", b"new").unwrap_err();
+
+        assert!(matches!(err, FileError::Rename { .. }));
+        assert_eq!(fs.paths(), vec![PathBuf::from("hello.txt")]);
+        assert_eq!(fs.contents("hello.txt").unwrap(), b"old");
+    }
+}
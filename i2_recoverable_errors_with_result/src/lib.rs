@@ -0,0 +1,6 @@
+// the error handling of main.rs, pulled out into a library so it can be reused and tested
+
+pub mod config;
+pub mod file;
+pub mod username;
+pub mod vfs;
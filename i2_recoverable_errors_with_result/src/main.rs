@@ -1,4 +1,7 @@
-fn main() {
+fn main() -> std::process::ExitCode {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     // RECOVERABLE ERRORS WITH RESULT
 
     // most errors aren't serious enough to require the program to stop entirely
@@ -130,6 +133,8 @@ fn main() {
     use std::io;
     use std::io::Read;
 
+    // (clippy suggests the ? operator here, which is where this is going)
+    #[allow(clippy::question_mark)]
     fn _read_username_from_file() -> Result<String, io::Error> {
         let f = File::open("hello4.txt");
 
@@ -256,4 +261,97 @@ fn main() {
     // the Box<dyn Error> type is called a trait object
     // for now you can read Box<dyn Error> to mean "any kind of error"
     // using ? in a mani function with this return type is allowed
+
+    // A CUSTOM ERROR TYPE
+
+    // the read_username_from_file functions above can only fail with an io::Error, but a file
+    // that opens and reads fine can still not contain a valid username
+    // src/username.rs defines a UsernameError enum with a variant for each reason and a checked
+    // Username type, so the calling code can match on the reason like it matches on the
+    // ErrorKind above
+
+    use i2_recoverable_errors_with_result::username::{self, UsernameError};
+    use i2_recoverable_errors_with_result::vfs::RealFs;
+    use i5_reporting::report::{self, Report};
+
+    // (RealFs is the filesystem on disk, the tests use a fake one in memory: see src/vfs.rs)
+    match username::read_username_from_file(&RealFs, "hello8.txt") {
+        Ok(name) => println!("hello {}", name),
+        Err(UsernameError::Io(e)) if e.kind() == ErrorKind::NotFound => {
+            println!("there's no hello8.txt yet, write a username in it")
+        }
+        Err(UsernameError::Empty) => println!("hello8.txt is empty, write a username in it"),
+        Err(e) => println!("error: {:?}", Report::from(e)),
+    }
+
+    // the errors of the library don't repeat the message of the error they come from (they
+    // return it from source instead), so printing one means walking the whole chain
+    // a Report (from i5_reporting, see below) prints the chain with {:?}:
+    // "error: the username is not valid UTF-8\n  caused by: invalid utf-8 sequence ..."
+
+    // OPENING OR CREATING A FILE, AGAIN
+
+    // the two versions at the top that open hello.txt or create it panic on any other error
+    // src/file.rs does the same thing in open_or_create, which returns a FileError instead and
+    // retries a few times when the error is likely to go away by itself
+    // write_atomic writes a file so that nobody can ever read it half written
+
+    use i2_recoverable_errors_with_result::file::{self, OpenPolicy};
+
+    // A CONFIGURATION IN LAYERS
+
+    // how many times open_or_create retries doesn't have to be written in the code: src/config.rs
+    // reads it (and the username) from app.toml, then from the APP_ environment variables, then
+    // from the command line, each one overriding the one before
+    //
+    // $ APP_RETRIES=5 cargo run -- --username ferris --backoff-ms 20
+    //
+    // a mistake names the layer and the key, like "command line: invalid value "x" for --retries"
+
+    use i2_recoverable_errors_with_result::config::Loader;
+
+    // --verbose is for report::main below, not a key of the configuration
+    let args = std::env::args()
+        .skip(1)
+        .filter(|arg| arg != report::VERBOSE_FLAG);
+    let config = Loader::new(&RealFs)
+        .file("app.toml")
+        .env(std::env::vars())
+        .args(args)
+        .load();
+
+    let policy = match config {
+        Ok(config) => {
+            println!("hello {} (from the configuration)", config.username);
+            config.open_policy
+        }
+        Err(e) => {
+            println!("error: {:?}", Report::from(e));
+            OpenPolicy::default()
+        }
+    };
+
+    // REPORTING ERRORS FROM MAIN
+
+    // main returning Result<(), Box<dyn Error>> prints the Debug of the error and exits with 1,
+    // whatever the error was
+    // i5_reporting::report does a bit more: report::main calls a function returning
+    // Result<T, Report>, and if it fails prints the error with all its causes (and the backtrace
+    // with --verbose) and exits with a code that says what went wrong, so a script can tell a
+    // missing file (66) from a permission problem (77)
+    // any error turns into a Report with ?, so the function reads like the ones above
+
+    let open_hello3 = || -> Result<(), Report> {
+        let opened = file::open_or_create(&RealFs, "hello3.txt", policy)?;
+        if opened.created {
+            println!("created hello3.txt");
+        } else {
+            println!("opened hello3.txt");
+        }
+
+        file::write_atomic(&RealFs, "hello3.txt", b"ferris\n")?;
+        Ok(())
+    };
+
+    report::main(open_hello3)
 }
@@ -0,0 +1,233 @@
+// A CUSTOM ERROR TYPE FOR READING A USERNAME
+
+// the _read_username_from_file functions in main.rs return io::Error, which can only say what
+// went wrong while opening or reading the file
+// but a file can be read just fine and still not contain a username: it can be empty, it can
+// contain bytes that aren't valid UTF-8, or the name in it can have characters a username can't
+// have
+// an enum with a variant for each reason lets the calling code match on what went wrong, like
+// main.rs matches on ErrorKind::NotFound
+
+use crate::vfs::FileSystem;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+use std::string::FromUtf8Error;
+
+// the longest username we accept (the same limit useradd has)
+pub const MAX_LEN: usize = 32;
+
+#[derive(Debug)]
+pub enum UsernameError {
+    // opening or reading the file failed
+    Io(io::Error),
+    NotUtf8(FromUtf8Error),
+    // nothing but whitespace in the file
+    Empty,
+    TooLong { len: usize, max: usize },
+    // position counts chars from 0
+    InvalidCharacter { character: char, position: usize },
+}
+
+impl fmt::Display for UsernameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UsernameError::Io(_) => write!(f, "failed to read the username"),
+            UsernameError::NotUtf8(_) => write!(f, "the username is not valid UTF-8"),
+            UsernameError::Empty => write!(f, "the username is empty"),
+            UsernameError::TooLong { len, max } => write!(
+                f,
+                "the username is {} characters long, the limit is {}",
+                len, max
+            ),
+            UsernameError::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "the username can't contain {:?} (character {})",
+                character,
+                position + 1
+            ),
+        }
+    }
+}
+
+// source gives back the error this one was made from, so the caller can walk the whole chain
+// (that's why Display doesn't repeat its message)
+impl Error for UsernameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UsernameError::Io(e) => Some(e),
+            UsernameError::NotUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// these From impls are what let the ? operator turn an io::Error (or a FromUtf8Error) into a
+// UsernameError
+impl From<io::Error> for UsernameError {
+    fn from(e: io::Error) -> UsernameError {
+        UsernameError::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for UsernameError {
+    fn from(e: FromUtf8Error) -> UsernameError {
+        UsernameError::NotUtf8(e)
+    }
+}
+
+// a username that has been checked (like the Guess of i3_to_panic_or_not_to_panic, the field is
+// private so every Username goes through new)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Username {
+    name: String,
+}
+
+impl Username {
+    // surrounding whitespace (like the newline at the end of a file) is dropped, then the name
+    // must be made of ASCII letters, digits, '_', '-' and '.'
+    pub fn new(name: &str) -> Result<Username, UsernameError> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(UsernameError::Empty);
+        }
+
+        let len = name.chars().count();
+        if len > MAX_LEN {
+            return Err(UsernameError::TooLong { len, max: MAX_LEN });
+        }
+
+        let invalid = name
+            .chars()
+            .enumerate()
+            .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '_' || *c == '-' || *c == '.'));
+        if let Some((position, character)) = invalid {
+            return Err(UsernameError::InvalidCharacter {
+                character,
+                position,
+            });
+        }
+
+        Ok(Username {
+            name: String::from(name),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FromStr for Username {
+    type Err = UsernameError;
+
+    fn from_str(s: &str) -> Result<Username, UsernameError> {
+        Username::new(s)
+    }
+}
+
+impl fmt::Display for Username {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+// _read_username_from_file2 of main.rs with the new error type: every ? converts its error with
+// the From impls above
+pub fn read_username_from_file<F, P>(fs: &F, path: P) -> Result<Username, UsernameError>
+where
+    F: FileSystem,
+    P: AsRef<Path>,
+{
+    let mut bytes = Vec::new();
+
+    fs.open(path.as_ref())?.read_to_end(&mut bytes)?;
+
+    Username::new(&String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::{Fault, MemoryFs, RealFs};
+    use std::fs;
+
+    #[test]
+    fn valid_usernames() {
+        assert_eq!(Username::new("ferris\n").unwrap().as_str(), "ferris");
+        assert_eq!(
+            "  john.doe-2_\t".parse::<Username>().unwrap().to_string(),
+            "john.doe-2_"
+        );
+    }
+
+    #[test]
+    fn invalid_usernames() {
+        assert!(matches!(Username::new(" \n"), Err(UsernameError::Empty)));
+        assert!(matches!(
+            Username::new(&"a".repeat(33)),
+            Err(UsernameError::TooLong { len: 33, max: 32 })
+        ));
+        assert!(matches!(
+            Username::new("fer ris"),
+            Err(UsernameError::InvalidCharacter {
+                character: ' ',
+                position: 3
+            })
+        ));
+        assert_eq!(
+            Username::new("niño").unwrap_err().to_string(),
+            "the username can't contain 'ñ' (character 3)"
+        );
+    }
+
+    #[test]
+    fn reading_from_files() {
+        let dir = std::env::temp_dir().join(format!("i2_username_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("ok.txt"), "ferris\n").unwrap();
+        fs::write(dir.join("latin1.txt"), b"ni\xf1o\n").unwrap();
+
+        let missing = read_username_from_file(&RealFs, dir.join("missing.txt")).unwrap_err();
+        let latin1 = read_username_from_file(&RealFs, dir.join("latin1.txt")).unwrap_err();
+        let ok = read_username_from_file(&RealFs, dir.join("ok.txt")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(&missing, UsernameError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+        assert!(missing.source().is_some());
+        assert!(matches!(latin1, UsernameError::NotUtf8(_)));
+        assert_eq!(ok.as_str(), "ferris");
+    }
+
+    #[test]
+    fn every_failure_has_its_variant() {
+        let fs = MemoryFs::new();
+        for name in &["denied.txt", "partial.txt", "latin1.txt", "empty.txt"] {
+            fs.add_file(name, b"ferris\n");
+        }
+        fs.add_file("empty.txt", b"\n");
+        fs.inject("denied.txt", Fault::PermissionDenied);
+        fs.inject("partial.txt", Fault::PartialRead(3));
+        fs.inject("latin1.txt", Fault::InvalidUtf8);
+
+        let read = |path| read_username_from_file(&fs, path).unwrap_err();
+
+        assert!(
+            matches!(read("missing.txt"), UsernameError::Io(e) if e.kind() == io::ErrorKind::NotFound)
+        );
+        assert!(
+            matches!(read("denied.txt"), UsernameError::Io(e) if e.kind() == io::ErrorKind::PermissionDenied)
+        );
+        assert!(
+            matches!(read("partial.txt"), UsernameError::Io(e) if e.kind() == io::ErrorKind::Other)
+        );
+        assert!(matches!(read("latin1.txt"), UsernameError::NotUtf8(_)));
+        assert!(matches!(read("empty.txt"), UsernameError::Empty));
+    }
+}
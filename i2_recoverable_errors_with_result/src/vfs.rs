@@ -0,0 +1,396 @@
+// A FILESYSTEM THAT CAN BE SWAPPED OUT
+
+// code that calls File::open directly can only be tested with real files, and some errors are
+// hard to get on purpose with real files: a permission denied needs a file we can't read (and
+// the tests may run as root), a read that fails halfway needs a broken disk
+// so the functions of this library don't call std::fs themselves: they take something that
+// implements FileSystem
+// RealFs forwards to std::fs, MemoryFs keeps the files in memory and fails on demand with the
+// errors injected for a path
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+pub trait FileSystem {
+    type File: Read + Write;
+
+    // opens an existing file for reading, like File::open (so a read-only file can be read)
+    fn open(&self, path: &Path) -> io::Result<Self::File>;
+
+    // opens an existing file for reading and writing
+    fn open_read_write(&self, path: &Path) -> io::Result<Self::File>;
+
+    // creates an empty file, or truncates the one that's there
+    fn create(&self, path: &Path) -> io::Result<Self::File>;
+
+    // creates an empty file, failing with AlreadyExists if there's one already
+    fn create_new(&self, path: &Path) -> io::Result<Self::File>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    // makes sure what was written to the file has reached the disk
+    fn sync(&self, file: &mut Self::File) -> io::Result<()>;
+}
+
+// the real filesystem
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    type File = File;
+
+    fn open(&self, path: &Path) -> io::Result<File> {
+        File::open(path)
+    }
+
+    fn open_read_write(&self, path: &Path) -> io::Result<File> {
+        OpenOptions::new().read(true).write(true).open(path)
+    }
+
+    fn create(&self, path: &Path) -> io::Result<File> {
+        File::create(path)
+    }
+
+    fn create_new(&self, path: &Path) -> io::Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn sync(&self, file: &mut File) -> io::Result<()> {
+        file.sync_all()
+    }
+}
+
+// the errors MemoryFs can be told to return for a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    // the path doesn't exist, even if there's a file with that name
+    NotFound,
+    PermissionDenied,
+    // the next n operations on the path fail with Interrupted (then they work again)
+    Interrupted(u32),
+    // reading gives the first n bytes of the file and then fails
+    PartialRead(usize),
+    // reading gives the contents of the file with a byte that can't be in UTF-8 in front
+    InvalidUtf8,
+    // a symlink to a file that doesn't exist: opening it fails with NotFound, creating it with
+    // create_new fails with AlreadyExists (the link is there)
+    DanglingLink,
+}
+
+type Contents = Rc<RefCell<Vec<u8>>>;
+
+#[derive(Debug, Default)]
+struct Disk {
+    files: HashMap<PathBuf, Contents>,
+    faults: HashMap<PathBuf, Fault>,
+}
+
+impl Disk {
+    // the error an operation on path must fail with, if any
+    fn check(&mut self, path: &Path) -> io::Result<()> {
+        match self.faults.get_mut(path) {
+            Some(Fault::NotFound) | Some(Fault::DanglingLink) => {
+                Err(io::Error::from(ErrorKind::NotFound))
+            }
+            Some(Fault::PermissionDenied) => Err(io::Error::from(ErrorKind::PermissionDenied)),
+            Some(Fault::Interrupted(0)) => Ok(()),
+            Some(Fault::Interrupted(times)) => {
+                *times -= 1;
+                Err(io::Error::from(ErrorKind::Interrupted))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn file(&self, path: &Path, contents: Contents) -> MemoryFile {
+        let mut data = contents.borrow().clone();
+        let mut limit = None;
+
+        match self.faults.get(path) {
+            Some(Fault::PartialRead(bytes)) => limit = Some(*bytes),
+            Some(Fault::InvalidUtf8) => data.insert(0, 0xff),
+            _ => {}
+        }
+
+        MemoryFile {
+            contents,
+            data,
+            position: 0,
+            limit,
+        }
+    }
+}
+
+// an in-memory filesystem: a map from paths to contents, with no directories
+// clones share the same files, so a test can keep one to look at what the code did with the
+// other
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    disk: Rc<RefCell<Disk>>,
+}
+
+impl MemoryFs {
+    pub fn new() -> MemoryFs {
+        MemoryFs::default()
+    }
+
+    pub fn add_file<P: AsRef<Path>>(&self, path: P, contents: &[u8]) {
+        let contents = Rc::new(RefCell::new(contents.to_vec()));
+
+        self.disk
+            .borrow_mut()
+            .files
+            .insert(path.as_ref().to_path_buf(), contents);
+    }
+
+    // from now on operations on path fail as fault says (replacing the previous fault)
+    pub fn inject<P: AsRef<Path>>(&self, path: P, fault: Fault) {
+        self.disk
+            .borrow_mut()
+            .faults
+            .insert(path.as_ref().to_path_buf(), fault);
+    }
+
+    pub fn contents<P: AsRef<Path>>(&self, path: P) -> Option<Vec<u8>> {
+        self.disk
+            .borrow()
+            .files
+            .get(path.as_ref())
+            .map(|contents| contents.borrow().clone())
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.disk.borrow().files.keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+}
+
+impl FileSystem for MemoryFs {
+    type File = MemoryFile;
+
+    fn open(&self, path: &Path) -> io::Result<MemoryFile> {
+        let mut disk = self.disk.borrow_mut();
+        disk.check(path)?;
+
+        let contents = disk
+            .files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::from(ErrorKind::NotFound))?;
+
+        Ok(disk.file(path, contents))
+    }
+
+    // the files in memory have no permissions, so this is the same as open
+    fn open_read_write(&self, path: &Path) -> io::Result<MemoryFile> {
+        self.open(path)
+    }
+
+    fn create(&self, path: &Path) -> io::Result<MemoryFile> {
+        let mut disk = self.disk.borrow_mut();
+        disk.check(path)?;
+
+        let contents = Contents::default();
+        disk.files.insert(path.to_path_buf(), contents.clone());
+
+        Ok(disk.file(path, contents))
+    }
+
+    fn create_new(&self, path: &Path) -> io::Result<MemoryFile> {
+        let mut disk = self.disk.borrow_mut();
+        if disk.faults.get(path) == Some(&Fault::DanglingLink) {
+            return Err(io::Error::from(ErrorKind::AlreadyExists));
+        }
+        disk.check(path)?;
+
+        if disk.files.contains_key(path) {
+            return Err(io::Error::from(ErrorKind::AlreadyExists));
+        }
+
+        let contents = Contents::default();
+        disk.files.insert(path.to_path_buf(), contents.clone());
+
+        Ok(disk.file(path, contents))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut disk = self.disk.borrow_mut();
+        disk.check(from)?;
+        disk.check(to)?;
+
+        let contents = disk
+            .files
+            .remove(from)
+            .ok_or_else(|| io::Error::from(ErrorKind::NotFound))?;
+        disk.files.insert(to.to_path_buf(), contents);
+
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut disk = self.disk.borrow_mut();
+        disk.check(path)?;
+
+        disk.files
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| io::Error::from(ErrorKind::NotFound))
+    }
+
+    fn sync(&self, _file: &mut MemoryFile) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// a file of MemoryFs: reads come from a copy of the contents taken when it was opened (with the
+// faults of its path applied), writes go straight to the filesystem
+#[derive(Debug)]
+pub struct MemoryFile {
+    contents: Contents,
+    data: Vec<u8>,
+    position: usize,
+    // reading fails after this many bytes
+    limit: Option<usize>,
+}
+
+impl Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let end = match self.limit {
+            Some(limit) if self.position >= limit => {
+                return Err(io::Error::other("read failed halfway"));
+            }
+            Some(limit) => limit.min(self.data.len()),
+            None => self.data.len(),
+        };
+
+        let available = &self.data[self.position.min(end)..end];
+        let n = available.len().min(buf.len());
+
+        buf[..n].copy_from_slice(&available[..n]);
+        self.position += n;
+
+        Ok(n)
+    }
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.contents.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(file: &mut MemoryFile) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    #[test]
+    fn files_in_memory() {
+        let fs = MemoryFs::new();
+        let path = Path::new("hello.txt");
+
+        assert_eq!(fs.open(path).unwrap_err().kind(), ErrorKind::NotFound);
+
+        fs.create(path).unwrap().write_all(b"hello").unwrap();
+        assert_eq!(read_all(&mut fs.open(path).unwrap()).unwrap(), b"hello");
+        assert_eq!(
+            fs.create_new(path).unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+
+        fs.rename(path, Path::new("world.txt")).unwrap();
+        assert_eq!(fs.paths(), vec![PathBuf::from("world.txt")]);
+        fs.remove_file(Path::new("world.txt")).unwrap();
+        assert!(fs.paths().is_empty());
+    }
+
+    #[test]
+    fn read_only_files_can_be_opened() {
+        let path = std::env::temp_dir().join(format!("i2_read_only_{}", std::process::id()));
+        fs::write(&path, "hello").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        let mut contents = String::new();
+        let read = RealFs
+            .open(&path)
+            .and_then(|mut file| file.read_to_string(&mut contents));
+        // opened for reading only: writing fails even for root, who can open anything
+        let write = RealFs.open(&path).and_then(|mut file| file.write_all(b"x"));
+        fs::remove_file(&path).unwrap();
+
+        assert!(read.is_ok());
+        assert_eq!(contents, "hello");
+        assert!(write.is_err());
+    }
+
+    #[test]
+    fn injected_faults() {
+        let fs = MemoryFs::new();
+        fs.add_file("a.txt", b"hello");
+        fs.add_file("b.txt", b"hello");
+        fs.add_file("c.txt", b"hello");
+
+        fs.inject("a.txt", Fault::NotFound);
+        assert_eq!(
+            fs.open(Path::new("a.txt")).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        fs.inject("a.txt", Fault::PermissionDenied);
+        assert_eq!(
+            fs.create(Path::new("a.txt")).unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+
+        fs.inject("a.txt", Fault::Interrupted(2));
+        for _ in 0..2 {
+            assert_eq!(
+                fs.open(Path::new("a.txt")).unwrap_err().kind(),
+                ErrorKind::Interrupted
+            );
+        }
+        assert!(fs.open(Path::new("a.txt")).is_ok());
+
+        fs.inject("b.txt", Fault::PartialRead(3));
+        let mut file = fs.open(Path::new("b.txt")).unwrap();
+        let mut buf = [0; 10];
+        assert_eq!(file.read(&mut buf).unwrap(), 3);
+        assert_eq!(file.read(&mut buf).unwrap_err().kind(), ErrorKind::Other);
+
+        fs.inject("c.txt", Fault::InvalidUtf8);
+        let bytes = read_all(&mut fs.open(Path::new("c.txt")).unwrap()).unwrap();
+        assert!(String::from_utf8(bytes).is_err());
+        assert_eq!(fs.contents("c.txt").unwrap(), b"hello");
+    }
+}
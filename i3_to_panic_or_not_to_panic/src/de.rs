@@ -0,0 +1,83 @@
+// SERDE SUPPORT (feature "serde")
+
+// a Bounded read from a file must be checked like any other: if Deserialize just filled the
+// private field, a 101 in a json file would become a Guess and the invariant would be gone
+// so deserializing reads an i64 and goes through Bounded::new, and the BoundsError becomes the
+// deserializer's error (something like "value must be at most 100, got 101 at line 1 column 12")
+
+// the Bounded doesn't know where it is in the document, only the deserializer does
+// deserialize (below) keeps track of the path, so the error also tells which field was wrong:
+// "guesses[2]: value must be at most 100, got 101 at line 1 column 24"
+
+use crate::Bounded;
+use serde::de::{Deserialize, DeserializeOwned, Deserializer, Error};
+use serde::ser::{Serialize, Serializer};
+
+impl<const MIN: i64, const MAX: i64> Serialize for Bounded<MIN, MAX> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.value())
+    }
+}
+
+impl<'de, const MIN: i64, const MAX: i64> Deserialize<'de> for Bounded<MIN, MAX> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Bounded<MIN, MAX>, D::Error> {
+        let value = i64::deserialize(deserializer)?;
+
+        Bounded::new(value).map_err(D::Error::custom)
+    }
+}
+
+// deserializes any value (like T::deserialize) and puts the path of the field that failed in
+// front of the error
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, serde_path_to_error::Error<D::Error>>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    serde_path_to_error::deserialize(deserializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Guess;
+
+    #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    struct Game {
+        player: String,
+        guesses: Vec<Guess>,
+    }
+
+    fn from_json(json: &str) -> Result<Game, String> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+
+        deserialize(&mut deserializer).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn round_trip() {
+        let game = from_json(r#"{"player": "ferris", "guesses": [50, 25, 37]}"#).unwrap();
+
+        assert_eq!(game.guesses[2].value(), 37);
+        assert_eq!(
+            serde_json::to_string(&game).unwrap(),
+            r#"{"player":"ferris","guesses":[50,25,37]}"#
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_rejected_with_their_path() {
+        assert_eq!(
+            from_json(r#"{"player": "ferris", "guesses": [50, 101]}"#).unwrap_err(),
+            "guesses[1]: value must be at most 100, got 101 at line 1 column 41"
+        );
+        assert!(from_json(r#"{"player": "ferris", "guesses": [0]}"#)
+            .unwrap_err()
+            .starts_with("guesses[0]: value must be at least 1, got 0"));
+        // plain serde_json still rejects it, only without the path
+        assert!(serde_json::from_str::<Guess>("-7")
+            .unwrap_err()
+            .to_string()
+            .starts_with("value must be at least 1, got -7"));
+    }
+}
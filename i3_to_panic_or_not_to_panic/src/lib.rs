@@ -0,0 +1,225 @@
+// CREATING CUSTOM TYPES FOR VALIDATION (WITHOUT PANICKING)
+
+// the Guess type of the book panics when it's given a value out of its range
+// that's the right call when a bad value can only come from a bug in the calling code, but
+// when the value comes from the user (like a guess typed in the guessing game) the calling code
+// wants to tell the user and ask again instead of crashing
+// so here the constructor returns a Result and the error says which bound was violated
+
+// the range is part of the type: MIN and MAX are const generic parameters, so a
+// Bounded<1, 100> and a Bounded<0, 9> are different types and a value of one can't be passed
+// where the other is expected
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+pub mod de;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bounded<const MIN: i64, const MAX: i64> {
+    value: i64,
+}
+
+// the guess of the guessing game is just a bounded integer between 1 and 100
+pub type Guess = Bounded<1, 100>;
+
+// which bound a value violated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundsError {
+    TooSmall { value: i64, min: i64 },
+    TooLarge { value: i64, max: i64 },
+}
+
+impl fmt::Display for BoundsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoundsError::TooSmall { value, min } => {
+                write!(f, "value must be at least {}, got {}", min, value)
+            }
+            BoundsError::TooLarge { value, max } => {
+                write!(f, "value must be at most {}, got {}", max, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BoundsError {}
+
+// parsing can fail because the text isn't a number or because the number is out of bounds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBoundedError {
+    Invalid(ParseIntError),
+    OutOfBounds(BoundsError),
+}
+
+impl fmt::Display for ParseBoundedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseBoundedError::Invalid(e) => write!(f, "not a number: {}", e),
+            ParseBoundedError::OutOfBounds(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseBoundedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseBoundedError::Invalid(e) => Some(e),
+            ParseBoundedError::OutOfBounds(e) => Some(e),
+        }
+    }
+}
+
+impl From<ParseIntError> for ParseBoundedError {
+    fn from(e: ParseIntError) -> ParseBoundedError {
+        ParseBoundedError::Invalid(e)
+    }
+}
+
+impl From<BoundsError> for ParseBoundedError {
+    fn from(e: BoundsError) -> ParseBoundedError {
+        ParseBoundedError::OutOfBounds(e)
+    }
+}
+
+impl<const MIN: i64, const MAX: i64> Bounded<MIN, MAX> {
+    pub const MIN: i64 = MIN;
+    pub const MAX: i64 = MAX;
+
+    // a type like Bounded<10, 1> could never hold a value, so it doesn't compile
+    // (the check runs when new is used with those parameters)
+    const NOT_EMPTY: () = assert!(MIN <= MAX, "Bounded needs MIN <= MAX");
+
+    pub fn new(value: i64) -> Result<Bounded<MIN, MAX>, BoundsError> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::NOT_EMPTY;
+
+        if value < MIN {
+            return Err(BoundsError::TooSmall { value, min: MIN });
+        }
+        if value > MAX {
+            return Err(BoundsError::TooLarge { value, max: MAX });
+        }
+
+        Ok(Bounded { value })
+    }
+
+    // getter method (the field stays private so every value goes through new)
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+
+    // checked arithmetic: like i64::checked_add and friends, None means the result would
+    // leave the bounds (or overflow i64)
+    pub fn checked_add(self, rhs: i64) -> Option<Bounded<MIN, MAX>> {
+        self.value
+            .checked_add(rhs)
+            .and_then(|value| Self::new(value).ok())
+    }
+
+    pub fn checked_sub(self, rhs: i64) -> Option<Bounded<MIN, MAX>> {
+        self.value
+            .checked_sub(rhs)
+            .and_then(|value| Self::new(value).ok())
+    }
+
+    pub fn checked_mul(self, rhs: i64) -> Option<Bounded<MIN, MAX>> {
+        self.value
+            .checked_mul(rhs)
+            .and_then(|value| Self::new(value).ok())
+    }
+
+    pub fn checked_div(self, rhs: i64) -> Option<Bounded<MIN, MAX>> {
+        self.value
+            .checked_div(rhs)
+            .and_then(|value| Self::new(value).ok())
+    }
+}
+
+impl<const MIN: i64, const MAX: i64> TryFrom<i64> for Bounded<MIN, MAX> {
+    type Error = BoundsError;
+
+    fn try_from(value: i64) -> Result<Bounded<MIN, MAX>, BoundsError> {
+        Bounded::new(value)
+    }
+}
+
+impl<const MIN: i64, const MAX: i64> From<Bounded<MIN, MAX>> for i64 {
+    fn from(bounded: Bounded<MIN, MAX>) -> i64 {
+        bounded.value
+    }
+}
+
+impl<const MIN: i64, const MAX: i64> FromStr for Bounded<MIN, MAX> {
+    type Err = ParseBoundedError;
+
+    fn from_str(s: &str) -> Result<Bounded<MIN, MAX>, ParseBoundedError> {
+        let value: i64 = s.trim().parse()?;
+
+        Ok(Bounded::new(value)?)
+    }
+}
+
+impl<const MIN: i64, const MAX: i64> fmt::Display for Bounded<MIN, MAX> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.value, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guess_keeps_its_range() {
+        assert_eq!(Guess::new(1).unwrap().value(), 1);
+        assert_eq!(Guess::new(100).unwrap().value(), 100);
+        assert_eq!(
+            Guess::new(0),
+            Err(BoundsError::TooSmall { value: 0, min: 1 })
+        );
+        assert_eq!(
+            Guess::try_from(101),
+            Err(BoundsError::TooLarge {
+                value: 101,
+                max: 100
+            })
+        );
+    }
+
+    #[test]
+    fn parses_and_displays() {
+        let guess: Guess = " 42\n".parse().unwrap();
+        assert_eq!(guess.to_string(), "42");
+        assert_eq!(format!("{:>4}", guess), "  42");
+
+        assert!(matches!(
+            "abc".parse::<Guess>(),
+            Err(ParseBoundedError::Invalid(_))
+        ));
+        assert_eq!(
+            "-5".parse::<Guess>(),
+            Err(ParseBoundedError::OutOfBounds(BoundsError::TooSmall {
+                value: -5,
+                min: 1
+            }))
+        );
+    }
+
+    #[test]
+    fn checked_arithmetic_stays_in_bounds() {
+        let digit = Bounded::<0, 9>::new(7).unwrap();
+
+        assert_eq!(digit.checked_add(2).map(i64::from), Some(9));
+        assert_eq!(digit.checked_add(3), None);
+        assert_eq!(digit.checked_sub(8), None);
+        assert_eq!(digit.checked_mul(0).map(i64::from), Some(0));
+        assert_eq!(digit.checked_div(0), None);
+
+        let wide = Bounded::<{ i64::MIN }, { i64::MAX }>::new(i64::MAX).unwrap();
+        assert_eq!(wide.checked_add(1), None);
+    }
+}
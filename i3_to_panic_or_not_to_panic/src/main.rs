@@ -1,25 +1,10 @@
-pub struct Guess {
-    value: i32,
-}
-
-impl Guess {
-    pub fn new(value: i32) -> Guess {
-        if value < 1 || value > 100 {
-            panic!("guess value musst be between 1 and 100, got {}", value);
-        }
-
-        Guess {
-            value
-        }
-    }
-
-    // getter method
-    pub fn value(&self) -> i32 {
-        self.value
-    }
-}
+use i3_to_panic_or_not_to_panic::{BoundsError, Bounded, Guess};
+use std::convert::TryFrom;
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     // TO PANIC OR NOT TO PANIC
     
     // how do you decide when you should call panic and when you should return Result?
@@ -148,15 +133,18 @@ fn main() {
     // the validations everywhere (in thies way, it's safe for functions to use the new type in
     // their signature and confidently use the values they receive)
     
-    // look above (before main)
+    // look at src/lib.rs
 
-   // the code in the body of the new function tests value to make sure it's between 1 and 100
-   // if value doesn't pass this test we make a panic! call which will alert the programmer who is
-   // writing the calling code that they have a bug they need to fix because creating a Guess with a
-   // value outside this range would violate the contract the Guess::new is relying on
+   // the code in the body of the new function tests value to make sure it's between MIN and MAX
+   // (1 and 100 for a Guess)
+   // the book's version makes a panic! call if value doesn't pass this test, which alerts the
+   // programmer who is writing the calling code that they have a bug they need to fix
+   // but a guess comes from the user, so an out of range value is expected to happen: here new
+   // returns a Result with a BoundsError that says which bound was violated, and the calling code
+   // can print it and ask again
    
    // we implement a method named value that borrows self, doesn't have any other parameters, and
-   // returns an i32
+   // returns an i64
    // this kind of method is sometimes called a getter, because its purpose is to get some data from
    // its fields and return it
    // this public method is necessary because the value field of the Guess struct is private
@@ -164,4 +152,25 @@ fn main() {
    // to set value directly: code outside the module must use the Guess::new function to create an
    // instance of Guess, thereby ensuring there's no way for a Guess to have a value that hasn't
    // been checked by the conditions in the Guess::new function
+
+    // the bounds are const generic parameters, so Guess is just an alias for Bounded<1, 100>
+    // (any other range is another type, like Bounded<0, 9> for a digit)
+
+    for input in ["42", "0", "101", "abc"] {
+        match input.parse::<Guess>() {
+            Ok(guess) => println!("{} is a valid guess", guess),
+            Err(e) => println!("{:?} is not a valid guess: {}", input, e),
+        }
+    }
+
+    match Guess::try_from(-3) {
+        Err(BoundsError::TooSmall { min, .. }) => println!("a guess starts from {}", min),
+        Err(BoundsError::TooLarge { max, .. }) => println!("a guess ends at {}", max),
+        Ok(guess) => println!("{}", guess.value()),
+    }
+
+    // checked arithmetic gives None instead of a value out of the bounds
+    let digit = Bounded::<0, 9>::new(8).unwrap();
+    println!("8 + 1 = {:?}", digit.checked_add(1).map(|d| d.value()));
+    println!("8 + 2 = {:?}", digit.checked_add(2).map(|d| d.value()));
 }
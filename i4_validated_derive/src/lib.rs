@@ -0,0 +1,533 @@
+// DERIVING VALIDATED NEWTYPES
+
+// the Guess type in i3_to_panic_or_not_to_panic is a newtype: a struct with a private field
+// that can only be built through a constructor that checks the value
+// writing one by hand means writing new, a getter, an error type, TryFrom and FromStr every
+// time, and it's easy to forget to keep the field private (then anyone can build an unchecked
+// value and the whole point is lost)
+// this derive writes all of that for a tuple struct with one private field:
+//
+// use i4_validated_derive::Validated;
+//
+// #[derive(Validated)]
+// #[validate(range = 1..=100)]
+// pub struct Guess(i32);
+//
+// #[derive(Validated)]
+// #[validate(regex = "^[a-z_][a-z0-9_]*$")]
+// pub struct Identifier(String);
+//
+// for Guess it generates:
+//
+// Guess::new(i32) -> Result<Guess, GuessError> and Guess::value(&self) -> i32
+// Guess::MIN and Guess::MAX
+// TryFrom<i32> for Guess, FromStr for Guess, From<Guess> for i32, Display for Guess
+// enum GuessError { TooSmall { value, min }, TooLarge { value, max }, Parse(ParseIntError) }
+// (with an f32 or f64 field there's also Unordered { value }, for a NaN: without it NaN would
+// pass every check, it's neither smaller nor larger than anything)
+//
+// for Identifier value returns a &str, there's a PATTERN const and the error is
+// enum IdentifierError { Mismatch { value } }
+//
+// ranges must be inclusive (a..=b, a.. or ..=b) so the error can say which value is allowed
+// the error type is named after the struct, #[validate(..., error = OtherName)] changes it
+// the regex is checked when deriving, but it's matched at runtime with the regex crate, so the
+// crate using #[validate(regex = "..")] must depend on regex too
+//
+// #[validate(..., serde)] gives the type Serialize and Deserialize too: deserializing goes
+// through new, so a value out of the rule is an error like "value must be at most 100, got 101"
+// (the crate using it must depend on serde, and serde_path_to_error can add the field path)
+// it's asked for on each type, so a crate that doesn't use serde never gets the impls; to put
+// them behind a feature of your own crate write #[cfg_attr(feature = "serde", validate(serde))]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, ExprRange, Fields, Ident, LitStr, RangeLimits, Type};
+
+#[proc_macro_derive(Validated, attributes(validate))]
+pub fn derive_validated(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// what a value is checked against
+enum Rule {
+    Range {
+        min: Option<Box<Expr>>,
+        max: Option<Box<Expr>>,
+    },
+    Regex(LitStr),
+}
+
+struct Settings {
+    rule: Rule,
+    error: Option<Ident>,
+    serde: bool,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let inner = inner_type(input)?;
+    let settings = settings(input)?;
+
+    let name = &input.ident;
+    let error = settings
+        .error
+        .unwrap_or_else(|| format_ident!("{}Error", name));
+    let common = common(name, &error, inner, settings.serde);
+
+    match settings.rule {
+        Rule::Range { min, max } => Ok(expand_range(
+            input,
+            &error,
+            inner,
+            min.as_deref(),
+            max.as_deref(),
+            common,
+        )),
+        Rule::Regex(pattern) => expand_regex(input, &error, inner, &pattern, common),
+    }
+}
+
+// the type of the only field, which must be private
+fn inner_type(input: &DeriveInput) -> syn::Result<&Type> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "Validated doesn't support generic types",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => fields,
+            _ => return Err(not_a_newtype(input)),
+        },
+        _ => return Err(not_a_newtype(input)),
+    };
+    let field = &fields.unnamed[0];
+
+    if let syn::Visibility::Inherited = field.vis {
+        Ok(&field.ty)
+    } else {
+        Err(syn::Error::new(
+            field.vis.span(),
+            "the field of a Validated type must be private, or values could be built without \
+             being checked",
+        ))
+    }
+}
+
+fn not_a_newtype(input: &DeriveInput) -> syn::Error {
+    syn::Error::new(
+        input.ident.span(),
+        "Validated can only be derived for a tuple struct with one field, like struct Guess(i32);",
+    )
+}
+
+// reads #[validate(range = a..=b)] or #[validate(regex = "..")], with an optional error = Name
+// and serde
+fn settings(input: &DeriveInput) -> syn::Result<Settings> {
+    let mut rule = None;
+    let mut error = None;
+    let mut serde = false;
+
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("validate")) {
+        attr.parse_nested_meta(|meta| {
+            if (meta.path.is_ident("range") || meta.path.is_ident("regex")) && rule.is_some() {
+                return Err(meta.error("only one range or regex is allowed"));
+            }
+
+            if meta.path.is_ident("range") {
+                let range: ExprRange = meta.value()?.parse()?;
+                rule = Some(range_rule(range)?);
+            } else if meta.path.is_ident("regex") {
+                let pattern: LitStr = meta.value()?.parse()?;
+                if let Err(e) = regex::Regex::new(&pattern.value()) {
+                    return Err(syn::Error::new(pattern.span(), e));
+                }
+                rule = Some(Rule::Regex(pattern));
+            } else if meta.path.is_ident("error") {
+                error = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("serde") {
+                serde = true;
+            } else {
+                return Err(meta.error("expected range, regex, error or serde"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    match rule {
+        Some(rule) => Ok(Settings { rule, error, serde }),
+        None => Err(syn::Error::new(
+            input.ident.span(),
+            "missing #[validate(range = a..=b)] or #[validate(regex = \"..\")]",
+        )),
+    }
+}
+
+fn range_rule(range: ExprRange) -> syn::Result<Rule> {
+    let span = range.span();
+
+    match (range.limits, &range.end) {
+        (RangeLimits::HalfOpen(_), Some(_)) => Err(syn::Error::new(
+            span,
+            "use an inclusive range (a..=b), so the error can tell the largest allowed value",
+        )),
+        (_, None) if range.start.is_none() => Err(syn::Error::new(span, "the range has no bounds")),
+        _ => Ok(Rule::Range {
+            min: range.start,
+            max: range.end,
+        }),
+    }
+}
+
+fn expand_range(
+    input: &DeriveInput,
+    error: &Ident,
+    inner: &Type,
+    min: Option<&Expr>,
+    max: Option<&Expr>,
+    common: TokenStream2,
+) -> TokenStream2 {
+    let name = &input.ident;
+    let vis = &input.vis;
+
+    // the bounds become consts, so they're written once and the comparisons don't trip the
+    // lints about comparing with the smallest or largest value of the type
+    let mut consts = TokenStream2::new();
+    let mut variants = TokenStream2::new();
+    let mut checks = TokenStream2::new();
+    let mut messages = TokenStream2::new();
+
+    // a float can be NaN, which is neither smaller nor larger than a bound, so its checks use
+    // partial_cmp (None when the value can't be compared) and the error gets an Unordered
+    // variant; the other types can't be unordered and keep plain comparisons
+    let float = last_segment_is(inner, &["f32", "f64"]);
+    let unordered = quote! {
+        ::std::option::Option::None => {
+            return ::std::result::Result::Err(#error::Unordered { value });
+        }
+    };
+
+    if let Some(min) = min {
+        consts.extend(quote! { pub const MIN: #inner = #min; });
+        variants.extend(quote! { TooSmall { value: #inner, min: #inner }, });
+        let too_small = quote! {
+            return ::std::result::Result::Err(#error::TooSmall { value, min: #name::MIN });
+        };
+        checks.extend(if float {
+            quote! {
+                match ::std::cmp::PartialOrd::partial_cmp(&value, &#name::MIN) {
+                    #unordered
+                    ::std::option::Option::Some(::std::cmp::Ordering::Less) => { #too_small }
+                    _ => {}
+                }
+            }
+        } else {
+            quote! {
+                if value < #name::MIN { #too_small }
+            }
+        });
+        messages.extend(quote! {
+            #error::TooSmall { value, min } => {
+                ::std::write!(f, "value must be at least {}, got {}", min, value)
+            }
+        });
+    }
+    if let Some(max) = max {
+        consts.extend(quote! { pub const MAX: #inner = #max; });
+        variants.extend(quote! { TooLarge { value: #inner, max: #inner }, });
+        let too_large = quote! {
+            return ::std::result::Result::Err(#error::TooLarge { value, max: #name::MAX });
+        };
+        checks.extend(if float {
+            quote! {
+                match ::std::cmp::PartialOrd::partial_cmp(&value, &#name::MAX) {
+                    #unordered
+                    ::std::option::Option::Some(::std::cmp::Ordering::Greater) => { #too_large }
+                    _ => {}
+                }
+            }
+        } else {
+            quote! {
+                if value > #name::MAX { #too_large }
+            }
+        });
+        messages.extend(quote! {
+            #error::TooLarge { value, max } => {
+                ::std::write!(f, "value must be at most {}, got {}", max, value)
+            }
+        });
+    }
+    if float {
+        variants.extend(quote! { Unordered { value: #inner }, });
+        messages.extend(quote! {
+            #error::Unordered { value } => {
+                ::std::write!(f, "value can't be compared with the bounds, got {}", value)
+            }
+        });
+    }
+
+    // the parse error names the type, "not a number" would be wrong for a range of chars
+    let parse_message = format!(
+        "not a valid {}: {{}}",
+        quote!(#inner).to_string().replace(' ', "")
+    );
+
+    quote! {
+        #[derive(Debug, Clone, PartialEq)]
+        #vis enum #error {
+            #variants
+            Parse(<#inner as ::std::str::FromStr>::Err),
+        }
+
+        impl ::std::fmt::Display for #error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match self {
+                    #messages
+                    #error::Parse(e) => ::std::write!(f, #parse_message, e),
+                }
+            }
+        }
+
+        impl ::std::error::Error for #error {
+            fn source(&self) -> ::std::option::Option<&(dyn ::std::error::Error + 'static)> {
+                match self {
+                    #error::Parse(e) => ::std::option::Option::Some(e),
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+
+        impl #name {
+            #consts
+
+            pub fn new(value: #inner) -> ::std::result::Result<#name, #error> {
+                #checks
+                ::std::result::Result::Ok(#name(value))
+            }
+
+            pub fn value(&self) -> #inner {
+                self.0
+            }
+        }
+
+        impl ::std::str::FromStr for #name {
+            type Err = #error;
+
+            fn from_str(s: &str) -> ::std::result::Result<#name, #error> {
+                let value: #inner = s.trim().parse().map_err(#error::Parse)?;
+                #name::new(value)
+            }
+        }
+
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        #common
+    }
+}
+
+fn expand_regex(
+    input: &DeriveInput,
+    error: &Ident,
+    inner: &Type,
+    pattern: &LitStr,
+    common: TokenStream2,
+) -> syn::Result<TokenStream2> {
+    if !last_segment_is(inner, &["String"]) {
+        return Err(syn::Error::new(
+            inner.span(),
+            "regex validation needs a String field",
+        ));
+    }
+
+    let name = &input.ident;
+    let vis = &input.vis;
+
+    Ok(quote! {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #vis enum #error {
+            Mismatch { value: #inner },
+        }
+
+        impl ::std::fmt::Display for #error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match self {
+                    #error::Mismatch { value } => {
+                        ::std::write!(f, "{:?} doesn't match {}", value, #name::PATTERN)
+                    }
+                }
+            }
+        }
+
+        impl ::std::error::Error for #error {}
+
+        impl #name {
+            pub const PATTERN: &'static str = #pattern;
+
+            // the whole value must match, not just a part of it
+            pub fn new(value: #inner) -> ::std::result::Result<#name, #error> {
+                static REGEX: ::std::sync::OnceLock<::regex::Regex> =
+                    ::std::sync::OnceLock::new();
+
+                let regex = REGEX.get_or_init(|| {
+                    ::regex::Regex::new(&::std::format!("^(?:{})$", #name::PATTERN))
+                        .expect("the pattern is checked when deriving")
+                });
+
+                if regex.is_match(&value) {
+                    ::std::result::Result::Ok(#name(value))
+                } else {
+                    ::std::result::Result::Err(#error::Mismatch { value })
+                }
+            }
+
+            pub fn value(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl ::std::str::FromStr for #name {
+            type Err = #error;
+
+            fn from_str(s: &str) -> ::std::result::Result<#name, #error> {
+                #name::new(::std::string::String::from(s))
+            }
+        }
+
+        impl ::std::convert::AsRef<str> for #name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        #common
+    })
+}
+
+// true for String, std::string::String, ... when names is ["String"]
+fn last_segment_is(ty: &Type, names: &[&str]) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| names.iter().any(|name| s.ident == name)),
+        _ => false,
+    }
+}
+
+// the conversions both kinds of rule share, and the serde impls if they were asked for
+fn common(name: &Ident, error: &Ident, inner: &Type, with_serde: bool) -> TokenStream2 {
+    let mut serde = TokenStream2::new();
+
+    if with_serde {
+        serde.extend(quote! {
+            impl ::serde::Serialize for #name {
+                fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+                where
+                    S: ::serde::Serializer,
+                {
+                    ::serde::Serialize::serialize(&self.0, serializer)
+                }
+            }
+
+            impl<'de> ::serde::Deserialize<'de> for #name {
+                fn deserialize<D>(deserializer: D) -> ::std::result::Result<#name, D::Error>
+                where
+                    D: ::serde::Deserializer<'de>,
+                {
+                    let value = <#inner as ::serde::Deserialize>::deserialize(deserializer)?;
+                    #name::new(value).map_err(::serde::de::Error::custom)
+                }
+            }
+        });
+    }
+
+    quote! {
+        #serde
+
+        impl ::std::convert::TryFrom<#inner> for #name {
+            type Error = #error;
+
+            fn try_from(value: #inner) -> ::std::result::Result<#name, #error> {
+                #name::new(value)
+            }
+        }
+
+        impl ::std::convert::From<#name> for #inner {
+            fn from(value: #name) -> #inner {
+                value.0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> String {
+        let input: DeriveInput = syn::parse_str(input).unwrap();
+
+        match expand(&input) {
+            Ok(_) => panic!("expected an error for {}", input.ident),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn rejects_public_fields() {
+        assert!(error("#[validate(range = 1..=100)] struct Guess(pub i32);").contains("private"));
+        assert!(
+            error("#[validate(range = 1..=100)] struct Guess(pub(crate) i32);").contains("private")
+        );
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_newtype() {
+        for input in &[
+            "#[validate(range = 1..=9)] struct Digit { value: u8 }",
+            "#[validate(range = 1..=9)] struct Digit(u8, u8);",
+            "#[validate(range = 1..=9)] enum Digit { One }",
+        ] {
+            assert!(error(input).contains("tuple struct"), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        assert!(error("struct Guess(i32);").contains("missing"));
+        assert!(error("#[validate(range = 1..100)] struct Guess(i32);").contains("inclusive"));
+        assert!(error("#[validate(range = ..)] struct Guess(i32);").contains("no bounds"));
+        assert!(
+            error("#[validate(range = 1..=2, regex = \"a\")] struct Guess(i32);")
+                .contains("only one")
+        );
+        assert!(error("#[validate(regex = \"(\")] struct Name(String);").contains("regex"));
+        assert!(error("#[validate(regex = \"a\")] struct Name(i32);").contains("String"));
+        assert!(
+            error("#[validate(range = 1..=2, serde = true)] struct Guess(i32);")
+                .contains("expected `,`")
+        );
+    }
+}
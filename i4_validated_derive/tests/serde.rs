@@ -0,0 +1,44 @@
+use i4_validated_derive::Validated;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Validated)]
+#[validate(range = 1..=100, serde)]
+pub struct Guess(i32);
+
+#[derive(Debug, Validated)]
+#[validate(regex = "[a-z_][a-z0-9_]*", serde)]
+pub struct Identifier(String);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Game {
+    player: Identifier,
+    guesses: Vec<Guess>,
+}
+
+fn from_json(json: &str) -> Result<Game, String> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+
+    serde_path_to_error::deserialize(&mut deserializer).map_err(|e| e.to_string())
+}
+
+#[test]
+fn round_trip() {
+    let game = from_json(r#"{"player": "ferris", "guesses": [50, 25]}"#).unwrap();
+
+    assert_eq!(game.player.value(), "ferris");
+    assert_eq!(
+        serde_json::to_string(&game).unwrap(),
+        r#"{"player":"ferris","guesses":[50,25]}"#
+    );
+}
+
+#[test]
+fn deserializing_checks_the_rule() {
+    assert_eq!(
+        from_json(r#"{"player": "ferris", "guesses": [50, 101]}"#).unwrap_err(),
+        "guesses[1]: value must be at most 100, got 101 at line 1 column 41"
+    );
+    assert!(from_json(r#"{"player": "Ferris", "guesses": []}"#)
+        .unwrap_err()
+        .starts_with("player: \"Ferris\" doesn't match [a-z_][a-z0-9_]*"));
+}
@@ -0,0 +1,113 @@
+use i4_validated_derive::Validated;
+use std::convert::TryFrom;
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Validated)]
+#[validate(range = 1..=100)]
+pub struct Guess(i32);
+
+// 0 is the smallest u8, the generated check must not warn about it
+#[derive(Debug, Validated)]
+#[validate(range = 0..=9, error = NotADigit)]
+struct Digit(u8);
+
+#[derive(Debug, Validated)]
+#[validate(range = -1.0..)]
+struct Correlation(f64);
+
+#[derive(Debug, PartialEq, Validated)]
+#[validate(regex = "[a-z_][a-z0-9_]*")]
+pub struct Identifier(String);
+
+#[test]
+fn range() {
+    assert_eq!(Guess::new(42).unwrap().value(), 42);
+    assert_eq!((Guess::MIN, Guess::MAX), (1, 100));
+    assert_eq!(
+        Guess::new(0),
+        Err(GuessError::TooSmall { value: 0, min: 1 })
+    );
+    assert_eq!(
+        Guess::try_from(101),
+        Err(GuessError::TooLarge {
+            value: 101,
+            max: 100
+        })
+    );
+    assert_eq!(i32::from(Guess::new(7).unwrap()), 7);
+
+    assert_eq!(
+        Digit::new(10).unwrap_err().to_string(),
+        "value must be at most 9, got 10"
+    );
+    assert!(matches!(Digit::new(0), Ok(Digit(0))));
+
+    assert!(Correlation::new(1e9).is_ok());
+    assert!(matches!(
+        Correlation::new(-2.0),
+        Err(CorrelationError::TooSmall { .. })
+    ));
+}
+
+#[test]
+fn nan_is_not_in_any_range() {
+    assert!(matches!(
+        Correlation::new(f64::NAN),
+        Err(CorrelationError::Unordered { .. })
+    ));
+    assert_eq!(
+        "NaN".parse::<Correlation>().unwrap_err().to_string(),
+        "value can't be compared with the bounds, got NaN"
+    );
+    assert_eq!(
+        "x".parse::<Correlation>().unwrap_err().to_string(),
+        "not a valid f64: invalid float literal"
+    );
+}
+
+// an integer can't be NaN, so its error has no Unordered variant to match
+#[test]
+fn integers_are_always_ordered() {
+    let kind = |e: GuessError| match e {
+        GuessError::TooSmall { .. } => "small",
+        GuessError::TooLarge { .. } => "large",
+        GuessError::Parse(_) => "parse",
+    };
+
+    assert_eq!(kind(Guess::new(0).unwrap_err()), "small");
+    assert_eq!(kind("x".parse::<Guess>().unwrap_err()), "parse");
+}
+
+#[test]
+fn range_from_str() {
+    let guess: Guess = " 50\n".parse().unwrap();
+    assert_eq!(guess.to_string(), "50");
+
+    let err = "fifty".parse::<Guess>().unwrap_err();
+    assert!(matches!(err, GuessError::Parse(_)));
+    assert!(err.to_string().starts_with("not a valid i32: "));
+    assert!(err.source().is_some());
+
+    assert!(matches!("-3".parse::<Digit>(), Err(NotADigit::Parse(_))));
+}
+
+#[test]
+fn regex() {
+    let id = Identifier::new(String::from("snake_case")).unwrap();
+    assert_eq!(id.value(), "snake_case");
+    assert_eq!(String::from(id), "snake_case");
+
+    // the whole value must match, not a part of it
+    assert_eq!(
+        "not valid".parse::<Identifier>(),
+        Err(IdentifierError::Mismatch {
+            value: String::from("not valid")
+        })
+    );
+    assert_eq!(
+        Identifier::try_from(String::from("9lives"))
+            .unwrap_err()
+            .to_string(),
+        "\"9lives\" doesn't match [a-z_][a-z0-9_]*"
+    );
+}
@@ -0,0 +1,286 @@
+// CRASH REPORTS
+
+// by default a panic prints its message and location to stderr (and a backtrace with
+// RUST_BACKTRACE=1), which is what a programmer wants and not much use to anyone else
+// the hook installed here writes all of that to a file in a crash report directory instead,
+// together with what's needed to understand the crash later (version, args, os, ...), and prints
+// a short message with the path of the file
+//
+// fn main() {
+//     i5_reporting::install_crash_report!();
+//     // --snip--
+// }
+//
+// the hook runs before the stack is unwound, and with panic = 'abort' before the process is
+// aborted, so the report is complete with both profiles
+// the environment variables are NOT written to the report (they often hold tokens and
+// passwords), only RUST_BACKTRACE, which changes what a panic prints
+
+use std::backtrace::Backtrace;
+use std::env;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// where the reports go when CRASH_REPORT_DIR is not set (relative to the working directory)
+pub const DEFAULT_DIR: &str = "crash-reports";
+pub const DIR_VARIABLE: &str = "CRASH_REPORT_DIR";
+
+// reports written by this process so far, so the threads that panic in the same second don't
+// write to the same file
+static REPORTS: AtomicU32 = AtomicU32::new(0);
+
+// installs the hook with the name and version of the crate it's called from
+#[macro_export]
+macro_rules! install_crash_report {
+    () => {
+        $crate::crash::install($crate::crash::Settings::new(
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        ))
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub name: String,
+    pub version: String,
+    pub dir: PathBuf,
+}
+
+impl Settings {
+    // the directory is $CRASH_REPORT_DIR, or DEFAULT_DIR
+    pub fn new(name: &str, version: &str) -> Settings {
+        let dir = env::var_os(DIR_VARIABLE)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DIR));
+
+        Settings {
+            name: String::from(name),
+            version: String::from(version),
+            dir,
+        }
+    }
+}
+
+// replaces the panic hook (for every thread)
+pub fn install(settings: Settings) {
+    panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::capture(&settings, info);
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+
+        // a panic hook must not panic, so errors writing to stderr are ignored
+        let _ = match report.write(&settings.dir) {
+            Ok(path) => writeln!(
+                stderr,
+                "{} crashed, sorry about that: {}\nthe details are in {}",
+                settings.name,
+                report.message,
+                path.display()
+            ),
+            Err(e) => writeln!(
+                stderr,
+                "{} crashed and the crash report couldn't be saved in {} ({}), here it is:\n\n{}",
+                settings.name,
+                settings.dir.display(),
+                e,
+                report.render()
+            ),
+        };
+    }));
+}
+
+// everything a crash report says
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    pub name: String,
+    pub version: String,
+    // seconds since the unix epoch
+    pub time: u64,
+    pub thread: String,
+    pub message: String,
+    // file:line:column
+    pub location: Option<String>,
+    pub args: Vec<String>,
+    // name and value, in the order they're written
+    pub environment: Vec<(String, String)>,
+    pub backtrace: String,
+}
+
+impl CrashReport {
+    pub fn capture(settings: &Settings, info: &PanicHookInfo) -> CrashReport {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let thread = thread::current()
+            .name()
+            .map(String::from)
+            .unwrap_or_else(|| String::from("<unnamed>"));
+
+        CrashReport {
+            name: settings.name.clone(),
+            version: settings.version.clone(),
+            time,
+            thread,
+            message: message(info.payload()),
+            location: info.location().map(|l| l.to_string()),
+            args: env::args_os()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            environment: environment(),
+            // captured even without RUST_BACKTRACE, it's the most useful part of the report
+            backtrace: Backtrace::force_capture().to_string(),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+
+        // writing to a String can't fail
+        let _ = writeln!(text, "crash report for {} {}", self.name, self.version);
+        let _ = writeln!(text, "time: {} (seconds since the unix epoch)", self.time);
+        let _ = writeln!(text, "thread: {}", self.thread);
+        let _ = writeln!(text, "message: {}", self.message);
+        let _ = writeln!(
+            text,
+            "location: {}",
+            self.location.as_deref().unwrap_or("unknown")
+        );
+        let _ = writeln!(text, "args: {:?}", self.args);
+        let _ = writeln!(text, "\nenvironment:");
+        for (name, value) in &self.environment {
+            let _ = writeln!(text, "  {}: {}", name, value);
+        }
+        let _ = writeln!(text, "\nbacktrace:\n{}", self.backtrace);
+
+        text
+    }
+
+    // writes the report to a new file in dir (creating dir if needed) and returns its path
+    // the name is name-time-pid-n.txt, where n counts the reports of the process: a file is
+    // never replaced, if the name is taken (a reused pid) the next n is tried
+    pub fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        loop {
+            let path = dir.join(format!(
+                "{}-{}-{}-{}.txt",
+                self.name,
+                self.time,
+                std::process::id(),
+                REPORTS.fetch_add(1, Ordering::Relaxed)
+            ));
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(self.render().as_bytes())?;
+                    return Ok(path);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+// the payload of panic!("...") is a &str, the one of panic!("{}", x) is a String
+pub fn message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        String::from(*message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("<not a string>")
+    }
+}
+
+fn environment() -> Vec<(String, String)> {
+    let strategy = if cfg!(panic = "abort") {
+        "abort"
+    } else {
+        "unwind"
+    };
+    let cwd = env::current_dir()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_else(|e| format!("unknown ({})", e));
+    let backtrace = env::var("RUST_BACKTRACE").unwrap_or_else(|_| String::from("not set"));
+
+    vec![
+        (
+            String::from("os"),
+            format!("{} {}", env::consts::OS, env::consts::ARCH),
+        ),
+        (String::from("working directory"), cwd),
+        (String::from("pid"), std::process::id().to_string()),
+        (String::from("panic strategy"), String::from(strategy)),
+        (String::from("RUST_BACKTRACE"), backtrace),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> CrashReport {
+        CrashReport {
+            name: String::from("demo"),
+            version: String::from("0.1.0"),
+            time: 42,
+            thread: String::from("main"),
+            message: String::from("crash and burn"),
+            location: Some(String::from("src/main.rs:3:5")),
+            args: vec![String::from("demo"), String::from("--flag")],
+            environment: vec![(String::from("os"), String::from("linux x86_64"))],
+            backtrace: String::from("0: main"),
+        }
+    }
+
+    #[test]
+    fn renders_every_field() {
+        let text = report().render();
+
+        for line in &[
+            "crash report for demo 0.1.0",
+            "message: crash and burn",
+            "location: src/main.rs:3:5",
+            "args: [\"demo\", \"--flag\"]",
+            "  os: linux x86_64",
+            "backtrace:\n0: main",
+        ] {
+            assert!(text.contains(line), "{:?} not in\n{}", line, text);
+        }
+    }
+
+    #[test]
+    fn writes_a_file_per_crash() {
+        let dir = env::temp_dir().join(format!("crash_reports_{}", std::process::id()));
+
+        // the same second, the same process
+        let first = report().write(&dir).unwrap();
+        let second = report().write(&dir).unwrap();
+        let text = fs::read_to_string(&first).unwrap();
+        let files = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let name = first.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with(&format!("demo-42-{}-", std::process::id())));
+        assert!(name.ends_with(".txt"));
+        assert_ne!(first, second);
+        assert_eq!(files, 2);
+        assert_eq!(text, report().render());
+    }
+
+    #[test]
+    fn payload_messages() {
+        assert_eq!(message(&"static"), "static");
+        assert_eq!(message(&String::from("formatted")), "formatted");
+        assert_eq!(message(&7), "<not a string>");
+    }
+}
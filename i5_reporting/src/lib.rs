@@ -0,0 +1,9 @@
+// what the lesson binaries share to tell the user that something went wrong
+//
+// crash: a panic hook that writes a crash report to a file and prints a short message instead
+// of the raw panic output
+// report: main returning a Result, printing the error with its causes and exiting with a code
+// that tells what went wrong
+
+pub mod crash;
+pub mod report;
@@ -0,0 +1,344 @@
+// REPORTING ERRORS FROM MAIN
+
+// main can return Result<(), E>: on Err rust prints the Debug of E and exits with status 1
+// that's enough for a lesson, but a program wants to say why it failed (the error and what
+// caused it, following source()) and to exit with a status scripts can tell apart
+// the statuses come from sysexits.h (the BSD convention most unix tools follow): 66 means an
+// input file is missing, 77 that the permission is lacking, and so on
+// (an error with no io::Error behind it is a bug or bad data as far as the exit code can tell:
+// 70, unless the program picks a better one with with_code)
+//
+// fn main() -> ExitCode {
+//     i5_reporting::report::main(run)
+// }
+//
+// fn run() -> Result<(), Report> {
+//     let name = fs::read_to_string("name.txt")?;
+//     // --snip--
+//     Ok(())
+// }
+//
+// any error converts into a Report with ?, and with --verbose the backtrace is captured right
+// there to show where the error came from (std's errors don't carry a backtrace)
+// capturing one is slow, so it's only done when the program asks for it with
+// capture_backtraces (report::main does it when --verbose is given)
+// .context("...") puts what the program was doing in front of the error:
+//
+// error: could not read name.txt
+//   caused by: No such file or directory (os error 2)
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind, Write};
+use std::process::{ExitCode, Termination};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// the codes of sysexits.h
+pub const EX_USAGE: u8 = 64;
+pub const EX_DATAERR: u8 = 65;
+pub const EX_NOINPUT: u8 = 66;
+pub const EX_UNAVAILABLE: u8 = 69;
+pub const EX_SOFTWARE: u8 = 70;
+pub const EX_CANTCREAT: u8 = 73;
+pub const EX_IOERR: u8 = 74;
+pub const EX_TEMPFAIL: u8 = 75;
+pub const EX_NOPERM: u8 = 77;
+
+pub const VERBOSE_FLAG: &str = "--verbose";
+
+// whether the Reports made from now on capture a backtrace
+static CAPTURE_BACKTRACES: AtomicBool = AtomicBool::new(false);
+
+pub fn capture_backtraces(capture: bool) {
+    CAPTURE_BACKTRACES.store(capture, Ordering::Relaxed);
+}
+
+// an error on its way out of main
+pub struct Report {
+    error: Box<dyn Error + Send + Sync + 'static>,
+    code: Option<u8>,
+    backtrace: Backtrace,
+}
+
+impl Report {
+    pub fn new<E>(error: E) -> Report
+    where
+        E: Into<Box<dyn Error + Send + Sync + 'static>>,
+    {
+        Report {
+            error: error.into(),
+            code: None,
+            backtrace: if CAPTURE_BACKTRACES.load(Ordering::Relaxed) {
+                Backtrace::force_capture()
+            } else {
+                Backtrace::disabled()
+            },
+        }
+    }
+
+    // a report for an error that is just a message
+    pub fn msg<M: fmt::Display>(message: M) -> Report {
+        Report::new(message.to_string())
+    }
+
+    // exits with code instead of the one worked out from the error
+    pub fn with_code(mut self, code: u8) -> Report {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn error(&self) -> &(dyn Error + 'static) {
+        &*self.error
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        self.code.unwrap_or_else(|| exit_code(self.error()))
+    }
+
+    // error: what went wrong
+    //   caused by: why
+    //   caused by: why that happened
+    // (and the backtrace, if verbose and it was captured)
+    pub fn write<W: Write>(&self, mut output: W, verbose: bool) -> io::Result<()> {
+        writeln!(output, "error: {}", self.error)?;
+        for cause in chain(self.error()).skip(1) {
+            writeln!(output, "  caused by: {}", cause)?;
+        }
+
+        if verbose && self.backtrace.status() == BacktraceStatus::Captured {
+            writeln!(output, "\nbacktrace:\n{}", self.backtrace)?;
+        }
+
+        Ok(())
+    }
+}
+
+// any error becomes a Report with ?
+impl<E: Error + Send + Sync + 'static> From<E> for Report {
+    fn from(error: E) -> Report {
+        Report::new(error)
+    }
+}
+
+// what main -> Result<(), Report> prints, if a program uses that instead of main below
+impl fmt::Debug for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        for cause in chain(self.error()).skip(1) {
+            write!(f, "\n  caused by: {}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+// what the program was doing when source happened
+#[derive(Debug)]
+pub struct ContextError {
+    context: String,
+    source: Box<dyn Error + Send + Sync + 'static>,
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.context)
+    }
+}
+
+impl Error for ContextError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+pub trait Context<T> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T, Report>;
+
+    // the message is only built if there's an error
+    fn with_context<C, F>(self, context: F) -> Result<T, Report>
+    where
+        C: fmt::Display,
+        F: FnOnce() -> C;
+}
+
+impl<T, E: Error + Send + Sync + 'static> Context<T> for Result<T, E> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T, Report> {
+        self.with_context(|| context)
+    }
+
+    fn with_context<C, F>(self, context: F) -> Result<T, Report>
+    where
+        C: fmt::Display,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|e| {
+            Report::new(ContextError {
+                context: context().to_string(),
+                source: Box::new(e),
+            })
+        })
+    }
+}
+
+// the error followed by its sources
+pub fn chain<'a>(
+    error: &'a (dyn Error + 'static),
+) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+    std::iter::successors(Some(error), |&e| e.source())
+}
+
+// the exit code for the first io::Error found in the chain
+// an error without an io::Error behind it gets EX_SOFTWARE
+pub fn exit_code(error: &(dyn Error + 'static)) -> u8 {
+    chain(error)
+        .find_map(|e| e.downcast_ref::<io::Error>())
+        .map_or(EX_SOFTWARE, |e| io_exit_code(e.kind()))
+}
+
+pub fn io_exit_code(kind: ErrorKind) -> u8 {
+    match kind {
+        ErrorKind::NotFound => EX_NOINPUT,
+        ErrorKind::PermissionDenied => EX_NOPERM,
+        ErrorKind::InvalidData | ErrorKind::InvalidInput | ErrorKind::UnexpectedEof => EX_DATAERR,
+        ErrorKind::AlreadyExists => EX_CANTCREAT,
+        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut => EX_TEMPFAIL,
+        ErrorKind::AddrInUse
+        | ErrorKind::AddrNotAvailable
+        | ErrorKind::ConnectionRefused
+        | ErrorKind::ConnectionReset => EX_UNAVAILABLE,
+        _ => EX_IOERR,
+    }
+}
+
+// true if --verbose is one of the arguments of the program
+pub fn verbose_requested() -> bool {
+    env::args().skip(1).any(|arg| arg == VERBOSE_FLAG)
+}
+
+// prints the report of a failed run to stderr and gives the exit code for it
+// a run that went fine exits as its value says (0 for (), or the ExitCode it returned)
+pub fn finish<T: Termination>(result: Result<T, Report>, verbose: bool) -> ExitCode {
+    match result {
+        Ok(value) => value.report(),
+        Err(report) => {
+            // nothing left to tell the user if stderr is gone too
+            let _ = report.write(io::stderr().lock(), verbose);
+            ExitCode::from(report.exit_code())
+        }
+    }
+}
+
+// runs the program and reports its error, with the backtrace if --verbose was given
+pub fn main<T, F>(run: F) -> ExitCode
+where
+    T: Termination,
+    F: FnOnce() -> Result<T, Report>,
+{
+    let verbose = verbose_requested();
+    capture_backtraces(verbose);
+
+    finish(run(), verbose)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Config {
+        source: io::Error,
+    }
+
+    impl fmt::Display for Config {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "could not load the config")
+        }
+    }
+
+    impl Error for Config {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.source)
+        }
+    }
+
+    fn config_error(kind: ErrorKind) -> Report {
+        Report::from(Config {
+            source: io::Error::new(kind, "app.toml"),
+        })
+    }
+
+    fn load(kind: ErrorKind) -> Result<(), io::Error> {
+        Err(io::Error::from(kind))
+    }
+
+    fn written(report: &Report, verbose: bool) -> String {
+        let mut output = Vec::new();
+        report.write(&mut output, verbose).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn prints_the_source_chain() {
+        let report = config_error(ErrorKind::NotFound);
+
+        assert_eq!(
+            written(&report, false),
+            "error: could not load the config\n  caused by: app.toml\n"
+        );
+        assert_eq!(
+            format!("{:?}", report),
+            "could not load the config\n  caused by: app.toml"
+        );
+    }
+
+    #[test]
+    fn exit_codes_follow_the_io_error() {
+        assert_eq!(config_error(ErrorKind::NotFound).exit_code(), 66);
+        assert_eq!(config_error(ErrorKind::PermissionDenied).exit_code(), 77);
+        assert_eq!(config_error(ErrorKind::InvalidData).exit_code(), EX_DATAERR);
+        assert_eq!(config_error(ErrorKind::Other).exit_code(), EX_IOERR);
+        assert_eq!(Report::msg("no").exit_code(), EX_SOFTWARE);
+        assert_eq!(
+            config_error(ErrorKind::NotFound)
+                .with_code(EX_USAGE)
+                .exit_code(),
+            EX_USAGE
+        );
+    }
+
+    #[test]
+    fn backtraces_only_when_asked() {
+        // the only test that changes it, the others don't look at backtraces
+        capture_backtraces(false);
+        let without = Report::msg("no");
+        capture_backtraces(true);
+        let with = Report::msg("no");
+        capture_backtraces(false);
+
+        assert_eq!(without.backtrace.status(), BacktraceStatus::Disabled);
+        assert_eq!(written(&without, true), "error: no\n");
+        assert!(written(&with, true).contains("\nbacktrace:\n"));
+        assert!(!written(&with, false).contains("backtrace"));
+    }
+
+    #[test]
+    fn context_goes_in_front_of_the_error() {
+        let report = load(ErrorKind::PermissionDenied)
+            .with_context(|| format!("could not read {}", "app.toml"))
+            .unwrap_err();
+
+        assert_eq!(
+            format!("{:?}", report),
+            "could not read app.toml\n  caused by: permission denied"
+        );
+        assert_eq!(report.exit_code(), EX_NOPERM);
+        assert!(load(ErrorKind::NotFound).context("loading").is_err());
+    }
+}
@@ -0,0 +1,35 @@
+// the hook is global to the process, so it gets a test binary of its own
+
+use i5_reporting::crash::{self, Settings};
+use std::fs;
+use std::thread;
+
+#[test]
+fn a_panic_writes_a_report() {
+    let dir = std::env::temp_dir().join(format!("crash_hook_{}", std::process::id()));
+    let mut settings = Settings::new("hook_test", "1.2.3");
+    settings.dir = dir.clone();
+    crash::install(settings);
+
+    let crashed = thread::Builder::new()
+        .name(String::from("worker"))
+        .spawn(|| {
+            let v: Vec<i32> = (1..=3).collect();
+            v[99]
+        })
+        .unwrap()
+        .join();
+    assert!(crashed.is_err());
+
+    let reports: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+    assert_eq!(reports.len(), 1);
+
+    let text = fs::read_to_string(reports[0].as_ref().unwrap().path()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(text.starts_with("crash report for hook_test 1.2.3\n"));
+    assert!(text.contains("thread: worker\n"));
+    assert!(text.contains("message: index out of bounds: the len is 3 but the index is 99\n"));
+    assert!(text.contains("location: tests/hook.rs:"));
+    assert!(text.contains("  panic strategy: unwind\n"));
+}
@@ -21,6 +21,9 @@ fn largest_char(list: &[char]) -> char {
 }
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     // GENERIC DATA TYPES
 
     // we can use generics to create definitions for items like
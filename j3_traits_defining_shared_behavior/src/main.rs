@@ -6,6 +6,9 @@ use lib::Summary3;
 use lib::Tweet;
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     // let's the necessary modules and types from our library lib.rs
     // (look also Cargo.toml for inclusion)
     // let's build an instance of our struct Tweet and let's use the
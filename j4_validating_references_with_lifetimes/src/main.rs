@@ -32,6 +32,9 @@ impl<'a> ImportantExcerpt<'a> {
 }
 
 fn main() {
+    // panics are reported by the hook of i5_reporting (see i1_unrecoverable_errors_with_panic)
+    i5_reporting::install_crash_report!();
+
     // VALIDATING REFERENCES WITH LIFETIMES
     
     // one detail we didn't discuss when we talked about references and lifetimes is